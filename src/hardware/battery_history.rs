@@ -0,0 +1,288 @@
+// Rolling battery sample log and runtime estimation
+// Samples are appended to a CSV file so the history survives restarts. They're buffered and
+// written every few minutes, not on every sample, to spare the SD card.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const HISTORY_MAX_AGE_SECS: u64 = 24 * 60 * 60;  // Keep one day of samples
+const ESTIMATE_WINDOW_SECS: u64 = 30 * 60;       // Fit the trend over the last 30 minutes
+const ESTIMATE_MIN_SPAN_SECS: u64 = 5 * 60;      // Need at least 5 minutes of data to estimate
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5 * 60); // Write buffered samples this often
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatterySample {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub voltage: f32,
+    pub percentage: f32,
+    pub is_charging: bool,
+}
+
+impl BatterySample {
+    pub fn now(voltage: f32, percentage: f32, is_charging: bool) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        Self { timestamp, voltage, percentage, is_charging }
+    }
+
+    fn to_csv(self) -> String {
+        format!("{},{:.3},{:.1},{}", self.timestamp, self.voltage, self.percentage, self.is_charging as u8)
+    }
+
+    fn from_csv(line: &str) -> Option<Self> {
+        let mut fields = line.split(',').map(str::trim);
+        Some(Self {
+            timestamp: fields.next()?.parse().ok()?,
+            voltage: fields.next()?.parse().ok()?,
+            percentage: fields.next()?.parse().ok()?,
+            is_charging: fields.next()? == "1",
+        })
+    }
+}
+
+/// Rolling history of battery samples, optionally backed by a CSV file
+pub struct BatteryHistory {
+    samples: VecDeque<BatterySample>,
+    path: Option<PathBuf>,
+    /// Lines in the file, so it can be compacted once old samples pile up
+    file_lines: usize,
+    /// Newest samples not written to the file yet
+    unsaved: usize,
+    last_flush: Instant,
+}
+
+impl BatteryHistory {
+    /// History that is not persisted
+    pub fn in_memory() -> Self {
+        Self {
+            samples: VecDeque::new(),
+            path: None,
+            file_lines: 0,
+            unsaved: 0,
+            last_flush: Instant::now(),
+        }
+    }
+
+    /// Load the history from disk (a missing or unreadable file starts empty)
+    pub fn load(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let contents = std::fs::read_to_string(&path).unwrap_or_default();
+        let mut history = Self {
+            samples: contents.lines().filter_map(BatterySample::from_csv).collect(),
+            path: Some(path),
+            file_lines: contents.lines().count(),
+            unsaved: 0,
+            last_flush: Instant::now(),
+        };
+        if let Some(latest) = history.samples.back().map(|s| s.timestamp) {
+            history.trim(latest);
+        }
+        history
+    }
+
+    pub fn samples(&self) -> &VecDeque<BatterySample> {
+        &self.samples
+    }
+
+    /// File the history is saved to, if any
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Add a sample, dropping anything older than a day. Written to the file every few minutes.
+    pub fn record(&mut self, sample: BatterySample) {
+        self.samples.push_back(sample);
+        self.unsaved += 1;
+        self.trim(sample.timestamp);
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    /// Write buffered samples to the file (also called before shutting down)
+    pub fn flush(&mut self) {
+        self.last_flush = Instant::now();
+        let Some(path) = &self.path else {
+            self.unsaved = 0;
+            return;
+        };
+        if self.unsaved == 0 {
+            return;
+        }
+
+        // Append normally; rewrite the whole file once it holds twice what we keep
+        let result = if self.file_lines + self.unsaved > self.samples.len() * 2 + 100 {
+            let contents: String = self.samples.iter().map(|s| s.to_csv() + "\n").collect();
+            std::fs::write(path, contents).map(|()| self.file_lines = self.samples.len())
+        } else {
+            let lines: String = self.samples.iter().skip(self.samples.len() - self.unsaved).map(|s| s.to_csv() + "\n").collect();
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(lines.as_bytes()))
+                .map(|()| self.file_lines += self.unsaved)
+        };
+
+        match result {
+            Ok(()) => self.unsaved = 0,
+            // Kept in memory and retried at the next flush
+            Err(e) => log::warn!("Could not write battery history to {}: {}", path.display(), e),
+        }
+    }
+
+    fn trim(&mut self, now: u64) {
+        while matches!(self.samples.front(), Some(s) if now.saturating_sub(s.timestamp) > HISTORY_MAX_AGE_SECS) {
+            self.samples.pop_front();
+        }
+        self.unsaved = self.unsaved.min(self.samples.len());
+    }
+
+    /// Remaining runtime when discharging, or time to full when charging.
+    /// Fits a line through recent samples since the charger was last plugged in or removed.
+    pub fn estimate(&self) -> Option<Duration> {
+        let latest = *self.samples.back()?;
+
+        // Stop at a clock step backwards (no RTC, NTP correction): older samples are on another timeline
+        let mut newer = latest.timestamp;
+        let recent: Vec<&BatterySample> = self
+            .samples
+            .iter()
+            .rev()
+            .take_while(|s| s.is_charging == latest.is_charging)
+            .take_while(|s| latest.timestamp.saturating_sub(s.timestamp) <= ESTIMATE_WINDOW_SECS)
+            .take_while(|s| {
+                let in_order = s.timestamp <= newer;
+                newer = s.timestamp;
+                in_order
+            })
+            .collect();
+
+        let oldest = recent.last()?;
+        if recent.len() < 3 || latest.timestamp.saturating_sub(oldest.timestamp) < ESTIMATE_MIN_SPAN_SECS {
+            return None;
+        }
+
+        // Least-squares slope in percent per second
+        let n = recent.len() as f64;
+        let t0 = oldest.timestamp;
+        let mean_t = recent.iter().map(|s| s.timestamp.saturating_sub(t0) as f64).sum::<f64>() / n;
+        let mean_p = recent.iter().map(|s| s.percentage as f64).sum::<f64>() / n;
+        let (mut covariance, mut variance) = (0.0, 0.0);
+        for s in &recent {
+            let dt = s.timestamp.saturating_sub(t0) as f64 - mean_t;
+            covariance += dt * (s.percentage as f64 - mean_p);
+            variance += dt * dt;
+        }
+        if variance <= 0.0 {
+            return None;
+        }
+        let slope = covariance / variance;
+
+        let seconds = if latest.is_charging {
+            (slope > 0.0).then(|| (100.0 - latest.percentage as f64).max(0.0) / slope)
+        } else {
+            (slope < 0.0).then(|| latest.percentage as f64 / -slope)
+        }?;
+
+        // Ignore nonsense from a nearly flat trend
+        (seconds < 7.0 * 24.0 * 3600.0).then(|| Duration::from_secs(seconds as u64))
+    }
+}
+
+/// Short label for an estimate, e.g. "~1h 20m" or "~45m"
+pub fn format_runtime(duration: Duration) -> String {
+    let minutes = (duration.as_secs() + 30) / 60;
+    if minutes >= 60 {
+        format!("~{}h {:02}m", minutes / 60, minutes % 60)
+    } else {
+        format!("~{}m", minutes.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, percentage: f32, is_charging: bool) -> BatterySample {
+        BatterySample { timestamp, voltage: 7.4, percentage, is_charging }
+    }
+
+    #[test]
+    fn test_discharge_estimate() {
+        let mut history = BatteryHistory::in_memory();
+        // Losing 1% per minute from 80%
+        for minute in 0..=10 {
+            history.record(sample(1_000 + minute * 60, 80.0 - minute as f32, false));
+        }
+        let estimate = history.estimate().unwrap().as_secs();
+        assert!((estimate as i64 - 70 * 60).abs() < 5);
+    }
+
+    #[test]
+    fn test_charging_estimate_uses_only_current_run() {
+        let mut history = BatteryHistory::in_memory();
+        for minute in 0..10 {
+            history.record(sample(minute * 60, 50.0 - minute as f32, false));
+        }
+        // Charger plugged in: too little charging data yet
+        history.record(sample(660, 42.0, true));
+        assert_eq!(history.estimate(), None);
+
+        // Gaining 2% per minute from 42%
+        for minute in 1..=6 {
+            history.record(sample(660 + minute * 60, 42.0 + 2.0 * minute as f32, true));
+        }
+        let estimate = history.estimate().unwrap().as_secs();
+        assert!((estimate as i64 - 23 * 60).abs() < 5);
+    }
+
+    #[test]
+    fn test_clock_step_backwards() {
+        let mut history = BatteryHistory::in_memory();
+        for minute in 0..=10 {
+            history.record(sample(100_000 + minute * 60, 80.0 - minute as f32, false));
+        }
+        // Clock jumps back an hour: only the samples after the jump count
+        history.record(sample(96_400, 69.0, false));
+        assert_eq!(history.estimate(), None);
+        for minute in 1..=10 {
+            history.record(sample(96_400 + minute * 60, 69.0 - minute as f32, false));
+        }
+        let estimate = history.estimate().unwrap().as_secs();
+        assert!((estimate as i64 - 59 * 60).abs() < 5);
+    }
+
+    #[test]
+    fn test_samples_are_buffered_until_flush() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("battery_history.csv");
+
+        let mut history = BatteryHistory::load(&path);
+        history.record(sample(1_000, 80.0, false));
+        history.record(sample(1_060, 79.0, false));
+        assert!(!path.exists(), "samples should not be written one by one");
+
+        history.flush();
+        history.record(sample(1_120, 78.0, false));
+        history.flush();
+        let reloaded = BatteryHistory::load(&path);
+        assert_eq!(reloaded.samples(), history.samples());
+    }
+
+    #[test]
+    fn test_csv_round_trip_and_format() {
+        let original = sample(1_700_000_000, 55.5, true);
+        assert_eq!(BatterySample::from_csv(&original.to_csv()), Some(original));
+        assert_eq!(BatterySample::from_csv("garbage"), None);
+
+        assert_eq!(format_runtime(Duration::from_secs(80 * 60)), "~1h 20m");
+        assert_eq!(format_runtime(Duration::from_secs(45 * 60)), "~45m");
+    }
+}
@@ -0,0 +1,192 @@
+// Battery discharge curves for turning pack voltage into state of charge
+// Li-ion and LiFePO4 curves are flat in the middle and steep at the knee,
+// so a straight line between empty and full misreports most of the range
+
+/// Resting voltage per cell -> state of charge for a 3.7V Li-ion cell
+const LI_ION_CELL: &[(f32, f32)] = &[
+    (3.00, 0.0),
+    (3.30, 5.0),
+    (3.50, 10.0),
+    (3.65, 20.0),
+    (3.70, 30.0),
+    (3.75, 40.0),
+    (3.80, 50.0),
+    (3.85, 60.0),
+    (3.92, 70.0),
+    (4.00, 80.0),
+    (4.10, 90.0),
+    (4.20, 100.0),
+];
+
+/// Resting voltage per cell -> state of charge for a 3.2V LiFePO4 cell
+const LIFEPO4_CELL: &[(f32, f32)] = &[
+    (2.50, 0.0),
+    (2.80, 5.0),
+    (3.00, 10.0),
+    (3.20, 20.0),
+    (3.22, 30.0),
+    (3.25, 40.0),
+    (3.26, 50.0),
+    (3.27, 60.0),
+    (3.30, 70.0),
+    (3.32, 80.0),
+    (3.35, 90.0),
+    (3.40, 100.0),
+];
+
+/// Piecewise-linear voltage -> state of charge table, sorted by voltage
+#[derive(Debug, Clone, PartialEq)]
+pub struct DischargeCurve {
+    points: Vec<(f32, f32)>,
+}
+
+impl Default for DischargeCurve {
+    /// 2S Li-ion, the pack used by the Harpy enclosure
+    fn default() -> Self {
+        Self::from_profile("liion_2s").expect("built-in profile")
+    }
+}
+
+impl DischargeCurve {
+    /// Built-in profile by name: "liion_<n>s" or "lifepo4_<n>s" (e.g. "liion_1s", "lifepo4_4s")
+    pub fn from_profile(name: &str) -> Option<DischargeCurve> {
+        let name = name.to_lowercase();
+        let (chemistry, cells) = name.rsplit_once('_')?;
+        let cells: f32 = cells.strip_suffix('s')?.parse::<u8>().ok().filter(|&n| n > 0)? as f32;
+
+        let cell_curve = match chemistry {
+            "liion" | "li-ion" | "lipo" => LI_ION_CELL,
+            "lifepo4" => LIFEPO4_CELL,
+            _ => return None,
+        };
+
+        Some(DischargeCurve {
+            points: cell_curve.iter().map(|&(v, soc)| (v * cells, soc)).collect(),
+        })
+    }
+
+    /// Custom table from config: "voltage:percent" pairs separated by commas,
+    /// e.g. "6.0:0, 7.0:20, 7.4:50, 8.4:100"
+    pub fn parse(table: &str) -> Result<DischargeCurve, String> {
+        let mut points = Vec::new();
+        for pair in table.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (voltage, soc) = pair
+                .split_once(':')
+                .ok_or_else(|| format!("Expected voltage:percent, got '{}'", pair))?;
+            let voltage: f32 = voltage.trim().parse().map_err(|_| format!("Invalid voltage '{}'", voltage.trim()))?;
+            let soc: f32 = soc.trim().parse().map_err(|_| format!("Invalid percentage '{}'", soc.trim()))?;
+            points.push((voltage, soc.clamp(0.0, 100.0)));
+        }
+
+        if points.len() < 2 {
+            return Err("A discharge curve needs at least two points".to_string());
+        }
+
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(DischargeCurve { points })
+    }
+
+    /// Pack voltage at empty and at full
+    pub fn voltage_range(&self) -> (f32, f32) {
+        (self.points[0].0, self.points[self.points.len() - 1].0)
+    }
+
+    /// Interpolate state of charge for a pack voltage (clamped to the table's ends)
+    pub fn percentage(&self, voltage: f32) -> f32 {
+        let (first, last) = (self.points[0], self.points[self.points.len() - 1]);
+        if voltage <= first.0 {
+            return first.1;
+        }
+        if voltage >= last.0 {
+            return last.1;
+        }
+
+        for pair in self.points.windows(2) {
+            let ((v0, s0), (v1, s1)) = (pair[0], pair[1]);
+            if voltage <= v1 {
+                if v1 - v0 <= f32::EPSILON {
+                    return s1;
+                }
+                return s0 + (voltage - v0) / (v1 - v0) * (s1 - s0);
+            }
+        }
+        last.1
+    }
+}
+
+/// Holds the displayed percentage until the reading moves by more than the band,
+/// so load spikes and ADC noise don't make the indicator jitter
+#[derive(Debug, Clone)]
+pub struct SocHysteresis {
+    band: f32,
+    shown: Option<f32>,
+    charging: bool,
+}
+
+impl SocHysteresis {
+    pub fn new(band: f32) -> Self {
+        Self {
+            band: band.max(0.0),
+            shown: None,
+            charging: false,
+        }
+    }
+
+    /// Feed a raw reading, returning the percentage to display
+    pub fn update(&mut self, raw: f32, is_charging: bool) -> f32 {
+        let shown = match self.shown {
+            // Plugging or unplugging the charger shifts the voltage; follow it immediately
+            Some(_) if is_charging != self.charging => raw,
+            Some(shown) if (raw - shown).abs() < self.band && raw > 0.0 && raw < 100.0 => shown,
+            _ => raw,
+        };
+
+        self.shown = Some(shown);
+        self.charging = is_charging;
+        shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_interpolation() {
+        let one_cell = DischargeCurve::from_profile("liion_1s").unwrap();
+        assert_eq!(one_cell.percentage(4.2), 100.0);
+        assert_eq!(one_cell.percentage(2.5), 0.0);
+        assert!((one_cell.percentage(3.775) - 45.0).abs() < 0.01);
+        assert_eq!(one_cell.voltage_range(), (3.0, 4.2));
+
+        // 2S doubles every voltage
+        let two_cell = DischargeCurve::from_profile("LiIon_2S").unwrap();
+        assert!((two_cell.percentage(7.55) - 45.0).abs() < 0.01);
+
+        assert!(DischargeCurve::from_profile("lifepo4_4s").is_some());
+        assert!(DischargeCurve::from_profile("nimh_2s").is_none());
+        assert!(DischargeCurve::from_profile("liion_0s").is_none());
+    }
+
+    #[test]
+    fn test_custom_curve() {
+        let curve = DischargeCurve::parse("8.4:100, 6.4:0, 7.4:50").unwrap();
+        assert_eq!(curve.percentage(7.4), 50.0);
+        assert_eq!(curve.percentage(6.9), 25.0);
+        assert_eq!(curve.percentage(9.0), 100.0);
+
+        assert!(DischargeCurve::parse("7.4:50").is_err());
+        assert!(DischargeCurve::parse("7.4-50, 8.4:100").is_err());
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut filter = SocHysteresis::new(3.0);
+        assert_eq!(filter.update(50.0, false), 50.0);
+        assert_eq!(filter.update(48.5, false), 50.0); // Within band, hold
+        assert_eq!(filter.update(51.0, false), 50.0);
+        assert_eq!(filter.update(46.0, false), 46.0); // Outside band, follow
+        assert_eq!(filter.update(47.0, true), 47.0);  // Charger plugged in, follow
+        assert_eq!(filter.update(0.0, true), 0.0);    // Empty is always shown
+    }
+}
@@ -3,10 +3,110 @@ use anyhow::{anyhow, Result};
 use image::{RgbImage, ImageBuffer};
 use std::path::Path;
 use std::io::Read;
-use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::process::Command;
 
+use super::camera_orientation::{FrameTransform, LensCorrectionCache};
+use super::gphoto_camera::GphotoCamera;
+
+/// Which camera hardware is used
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CameraBackendKind {
+    /// Raspberry Pi camera module via rpicam-vid/rpicam-still
+    #[default]
+    Libcamera,
+    /// Tethered DSLR via gphoto2
+    Gphoto2,
+}
+
+/// Backend-specific state
+enum CameraBackend {
+    Libcamera,
+    Gphoto2(GphotoCamera),
+}
+
+impl CameraBackend {
+    fn from_config(config: &CameraConfig) -> Self {
+        match config.backend {
+            CameraBackendKind::Libcamera => CameraBackend::Libcamera,
+            CameraBackendKind::Gphoto2 => CameraBackend::Gphoto2(GphotoCamera::new(&config.gphoto2_binary)),
+        }
+    }
+}
+
+/// How a photo is taken when the shutter button is pressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureMode {
+    /// Grab the current frame from the live stream (zero shutter lag)
+    Stream,
+    /// Stop the stream and take a full-quality still with rpicam-still
+    Still,
+}
+
+impl CaptureMode {
+    pub fn all() -> &'static [CaptureMode] {
+        &[CaptureMode::Stream, CaptureMode::Still]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            CaptureMode::Stream => "Instant",
+            CaptureMode::Still => "HQ",
+        }
+    }
+
+    pub fn next(&self) -> CaptureMode {
+        let all = Self::all();
+        let idx = all.iter().position(|x| x == self).unwrap();
+        all[(idx + 1) % all.len()]
+    }
+
+    /// Mode to try when this one fails
+    pub fn fallback(&self) -> CaptureMode {
+        match self {
+            CaptureMode::Stream => CaptureMode::Still,
+            CaptureMode::Still => CaptureMode::Stream,
+        }
+    }
+}
+
+/// Camera configuration (loaded from the [camera] section of pixelsort.toml)
+#[derive(Clone)]
+pub struct CameraConfig {
+    /// Mounting correction applied to preview frames and stills
+    pub transform: FrameTransform,
+    /// Camera hardware to use
+    pub backend: CameraBackendKind,
+    /// gphoto2 executable (only used by the Gphoto2 backend)
+    pub gphoto2_binary: String,
+    /// Still capture size and JPEG quality
+    pub capture_width: u32,
+    pub capture_height: u32,
+    pub quality: u8,
+    /// Live preview stream size
+    pub preview_width: u32,
+    pub preview_height: u32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self {
+            transform: FrameTransform::default(),
+            backend: CameraBackendKind::Libcamera,
+            gphoto2_binary: "gphoto2".to_string(),
+            // Match screen resolution (1024x600) for full-screen preview
+            capture_width: 1024,
+            capture_height: 600,
+            quality: 90,  // High quality for pixel sorting
+            preview_width: 1024,
+            preview_height: 600,
+        }
+    }
+}
+
 /// Camera controller for Raspberry Pi Camera v1.5 using libcamera
 /// Uses streaming approach for live preview + on-demand still capture
 pub struct CameraController {
@@ -27,40 +127,58 @@ pub struct CameraController {
     /// Channel for receiving frames from streaming thread
     frame_receiver: Option<Receiver<RgbImage>>,
     /// Channel for sending frames to main thread
-    frame_sender: Option<Sender<RgbImage>>,
+    frame_sender: Option<SyncSender<RgbImage>>,
+    /// Most recent frame received from the stream (used for instant capture)
+    latest_frame: Option<RgbImage>,
     /// Streaming thread handle
     stream_thread: Option<thread::JoinHandle<()>>,
     /// Whether streaming is active
     streaming_active: bool,
+    /// Mounting correction, shared with the streaming thread so changes apply immediately
+    transform: Arc<Mutex<FrameTransform>>,
+    /// Camera hardware backend
+    backend: CameraBackend,
+    /// Tells the gphoto2 live-view thread to stop
+    stream_stop: Arc<AtomicBool>,
 }
 
 impl CameraController {
     /// Create a new camera controller
-    pub fn new() -> Result<Self> {
+    pub fn new(config: CameraConfig) -> Result<Self> {
         let mut controller = CameraController {
-            // Match screen resolution for consistent display
-            capture_width: 1024,
-            capture_height: 600,
-            quality: 90,  // High quality for pixel sorting
-            // Match screen resolution (1024x600) for full-screen preview
-            preview_width: 1024,
-            preview_height: 600,
+            capture_width: config.capture_width,
+            capture_height: config.capture_height,
+            quality: config.quality,
+            preview_width: config.preview_width,
+            preview_height: config.preview_height,
             temp_capture_path: "/tmp/pixelsort_capture.jpg".to_string(),
             temp_preview_path: "/tmp/pixelsort_preview.jpg".to_string(),
             is_available: false,
             stream_process: None,
             frame_receiver: None,
             frame_sender: None,
+            latest_frame: None,
             stream_thread: None,
             streaming_active: false,
+            transform: Arc::new(Mutex::new(config.transform)),
+            backend: CameraBackend::from_config(&config),
+            stream_stop: Arc::new(AtomicBool::new(false)),
         };
 
         controller.initialize()?;
         Ok(controller)
     }
 
-    /// Initialize the camera by checking if rpicam-still is available
+    /// Initialize the camera by checking if rpicam-still (or gphoto2) is available
     pub fn initialize(&mut self) -> Result<()> {
+        if let CameraBackend::Gphoto2(gphoto) = &self.backend {
+            self.is_available = gphoto.detect();
+            if self.is_available {
+                log::info!("Tethered camera initialized successfully (using gphoto2)");
+            }
+            return Ok(()); // Don't fail completely, just disable camera
+        }
+
         // Check if rpicam-still command is available
         match Command::new("rpicam-still").arg("--help").output() {
             Ok(_) => {
@@ -100,6 +218,53 @@ impl CameraController {
         self.quality = quality.min(100);
     }
 
+    /// Set the mounting correction (rotation, flips, lens distortion)
+    pub fn set_transform(&mut self, transform: FrameTransform) {
+        if let Ok(mut current) = self.transform.lock() {
+            *current = transform;
+        }
+    }
+
+    /// Current mounting correction
+    pub fn transform(&self) -> FrameTransform {
+        self.transform.lock().map(|t| *t).unwrap_or_default()
+    }
+
+    /// Apply a reloaded configuration. Stream size and backend changes restart the live stream.
+    pub fn apply_config(&mut self, config: &CameraConfig) {
+        self.set_transform(config.transform);
+        self.set_quality(config.quality);
+        self.capture_width = config.capture_width;
+        self.capture_height = config.capture_height;
+
+        let backend_changed = match (&self.backend, config.backend) {
+            (CameraBackend::Libcamera, CameraBackendKind::Libcamera) => false,
+            (CameraBackend::Gphoto2(gphoto), CameraBackendKind::Gphoto2) => gphoto.binary() != config.gphoto2_binary,
+            _ => true,
+        };
+        let preview_changed = (self.preview_width, self.preview_height) != (config.preview_width, config.preview_height);
+        if !backend_changed && !preview_changed {
+            return;
+        }
+
+        let was_streaming = self.streaming_active;
+        self.stop_streaming();
+        self.preview_width = config.preview_width;
+        self.preview_height = config.preview_height;
+
+        if backend_changed {
+            log::info!("Switching camera backend to {:?}", config.backend);
+            self.backend = CameraBackend::from_config(config);
+            let _ = self.initialize();
+        }
+
+        if was_streaming {
+            if let Err(e) = self.start_streaming() {
+                log::error!("Failed to restart camera stream: {}", e);
+            }
+        }
+    }
+
     /// Check if camera is available and working
     pub fn is_available(&self) -> bool {
         self.is_available
@@ -111,11 +276,17 @@ impl CameraController {
             return Ok(());
         }
 
-        // Create channel for frame communication
-        let (sender, receiver) = mpsc::channel();
+        // Create channel for frame communication (bounded so frames are dropped,
+        // not queued, while nobody is reading the preview)
+        let (sender, receiver) = mpsc::sync_channel(2);
         self.frame_sender = Some(sender);
         self.frame_receiver = Some(receiver);
 
+        if let CameraBackend::Gphoto2(gphoto) = &self.backend {
+            self.start_gphoto_live_view(gphoto.clone());
+            return Ok(());
+        }
+
         // Start streaming process
         let process = Command::new("rpicam-vid")
             .args([
@@ -136,9 +307,11 @@ impl CameraController {
         // Start background thread to read frames
         let frame_sender = self.frame_sender.as_ref().unwrap().clone();
         let mut stdout = self.stream_process.as_mut().unwrap().stdout.take().unwrap();
+        let transform = Arc::clone(&self.transform);
 
         let stream_thread = thread::spawn(move || {
             let mut buffer = Vec::new();
+            let mut lens_cache = LensCorrectionCache::default();
 
             loop {
                 let mut temp_buf = [0u8; 4096];
@@ -156,9 +329,10 @@ impl CameraController {
 
                                     // Decode JPEG frame
                                     if let Ok(img) = image::load_from_memory_with_format(jpeg_data, image::ImageFormat::Jpeg) {
-                                        let rgb_img = img.to_rgb8();
-                                        // Send frame to main thread (non-blocking)
-                                        let _ = frame_sender.send(rgb_img);
+                                        let frame_transform = transform.lock().map(|t| *t).unwrap_or_default();
+                                        let rgb_img = frame_transform.apply(img.to_rgb8(), &mut lens_cache);
+                                        // Send frame to main thread (non-blocking, drops frame if full)
+                                        let _ = frame_sender.try_send(rgb_img);
                                     }
 
                                     // Remove processed data
@@ -183,9 +357,40 @@ impl CameraController {
         Ok(())
     }
 
+    /// Poll gphoto2 live view in a background thread, feeding the same frame channel as rpicam-vid
+    fn start_gphoto_live_view(&mut self, gphoto: GphotoCamera) {
+        let frame_sender = self.frame_sender.as_ref().unwrap().clone();
+        let transform = Arc::clone(&self.transform);
+        let stop = Arc::clone(&self.stream_stop);
+        stop.store(false, Ordering::Relaxed);
+
+        let stream_thread = thread::spawn(move || {
+            let mut lens_cache = LensCorrectionCache::default();
+
+            while !stop.load(Ordering::Relaxed) {
+                match gphoto.capture_preview() {
+                    Ok(frame) => {
+                        let frame_transform = transform.lock().map(|t| *t).unwrap_or_default();
+                        let _ = frame_sender.try_send(frame_transform.apply(frame, &mut lens_cache));
+                    }
+                    Err(e) => {
+                        log::debug!("gphoto2 live view frame failed: {}", e);
+                        thread::sleep(std::time::Duration::from_millis(500));
+                    }
+                }
+            }
+        });
+
+        self.stream_thread = Some(stream_thread);
+        self.streaming_active = true;
+
+        log::info!("Camera live view started (gphoto2)");
+    }
+
     /// Stop camera streaming
     pub fn stop_streaming(&mut self) {
         self.streaming_active = false;
+        self.stream_stop.store(true, Ordering::Relaxed);
 
         // Kill the streaming process
         if let Some(mut process) = self.stream_process.take() {
@@ -201,6 +406,7 @@ impl CameraController {
         // Clear channels
         self.frame_sender = None;
         self.frame_receiver = None;
+        self.latest_frame = None;
 
         log::info!("Camera streaming stopped");
     }
@@ -227,6 +433,7 @@ impl CameraController {
             }
 
             if let Some(frame) = latest_frame {
+                self.latest_frame = Some(frame.clone());
                 return Ok(frame);
             }
         }
@@ -235,6 +442,79 @@ impl CameraController {
         self.get_test_pattern()
     }
 
+    /// Grab the current frame from the live stream without stopping it
+    pub fn capture_stream_frame(&mut self) -> Result<RgbImage> {
+        if !self.is_available {
+            return Err(anyhow!("Camera not available"));
+        }
+        if !self.streaming_active {
+            return Err(anyhow!("Camera stream not running"));
+        }
+
+        // Prefer a frame that arrived since the last preview update
+        if let Some(receiver) = &self.frame_receiver {
+            while let Ok(frame) = receiver.try_recv() {
+                self.latest_frame = Some(frame);
+            }
+        }
+
+        self.latest_frame
+            .clone()
+            .ok_or_else(|| anyhow!("No frame received from stream yet"))
+    }
+
+    /// Drain every frame received from the stream since the last call, oldest first.
+    /// Used by modes that need each successive frame rather than just the latest.
+    pub fn take_stream_frames(&mut self) -> Vec<RgbImage> {
+        if !self.streaming_active {
+            if let Err(e) = self.start_streaming() {
+                log::error!("Failed to start camera stream: {}", e);
+            }
+        }
+
+        let mut frames = Vec::new();
+        if let Some(receiver) = &self.frame_receiver {
+            while let Ok(frame) = receiver.try_recv() {
+                frames.push(frame);
+            }
+        }
+        if let Some(last) = frames.last() {
+            self.latest_frame = Some(last.clone());
+        }
+        frames
+    }
+
+    /// Take a photo using the given mode, falling back to the other mode if it fails.
+    /// The live stream is always restored afterwards.
+    pub fn capture(&mut self, mode: CaptureMode) -> Result<RgbImage> {
+        let result = match mode {
+            CaptureMode::Stream => self.capture_stream_frame().or_else(|e| {
+                log::warn!("{} capture failed ({}), falling back to {}", mode.name(), e, mode.fallback().name());
+                self.capture_still()
+            }),
+            CaptureMode::Still => {
+                // Stopping the stream for the still discards its frames, so keep one first
+                let stream_frame = self.capture_stream_frame();
+                self.capture_still().or_else(|e| {
+                    log::warn!("{} capture failed ({}), falling back to {}", mode.name(), e, mode.fallback().name());
+                    stream_frame
+                })
+            }
+        };
+
+        if let Err(e) = self.start_streaming() {
+            log::error!("Failed to restore camera stream after capture: {}", e);
+        }
+
+        result
+    }
+
+    fn capture_still(&mut self) -> Result<RgbImage> {
+        // Stop streaming to free camera for high-quality capture
+        self.stop_streaming();
+        self.capture_snapshot()
+    }
+
     /// Get the latest preview image (now uses streaming)
     pub fn get_preview_image(&mut self) -> Result<RgbImage> {
         self.get_fast_preview_image()
@@ -300,6 +580,14 @@ impl CameraController {
             return Err(anyhow!("Camera not available"));
         }
 
+        if let CameraBackend::Gphoto2(gphoto) = &self.backend {
+            // DSLR photos are far larger than the screen; scale to the capture size like rpicam
+            let photo = image::DynamicImage::ImageRgb8(gphoto.capture_image()?)
+                .resize(self.capture_width, self.capture_height, image::imageops::FilterType::Triangle)
+                .to_rgb8();
+            return Ok(self.transform().apply(photo, &mut LensCorrectionCache::default()));
+        }
+
         // Remove any existing capture file
         if Path::new(&self.temp_capture_path).exists() {
             let _ = std::fs::remove_file(&self.temp_capture_path);
@@ -332,7 +620,7 @@ impl CameraController {
         // Load and return the captured image
         match image::open(&self.temp_capture_path) {
             Ok(img) => {
-                let rgb_img = img.to_rgb8();
+                let rgb_img = self.transform().apply(img.to_rgb8(), &mut LensCorrectionCache::default());
                 // Clean up temp file
                 let _ = std::fs::remove_file(&self.temp_capture_path);
                 Ok(rgb_img)
@@ -362,4 +650,39 @@ impl Drop for CameraController {
             let _ = std::fs::remove_file(&self.temp_preview_path);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    /// A controller whose stream is running but whose stills always fail
+    fn controller_with_failing_still() -> CameraController {
+        let config = CameraConfig {
+            backend: CameraBackendKind::Gphoto2,
+            gphoto2_binary: "false".to_string(),
+            ..CameraConfig::default()
+        };
+        let mut camera = CameraController::new(config).unwrap();
+        camera.is_available = true;
+        camera.start_streaming().unwrap();
+        camera
+    }
+
+    #[test]
+    fn failed_still_falls_back_to_last_stream_frame() {
+        let mut camera = controller_with_failing_still();
+        let frame = RgbImage::from_pixel(8, 8, image::Rgb([10, 20, 30]));
+        camera.latest_frame = Some(frame.clone());
+
+        let captured = camera.capture(CaptureMode::Still).unwrap();
+        assert_eq!(captured, frame);
+        assert!(camera.streaming_active);
+    }
+
+    #[test]
+    fn failed_still_without_stream_frame_is_an_error() {
+        let mut camera = controller_with_failing_still();
+        assert!(camera.capture(CaptureMode::Still).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use image::{imageops, RgbImage};
+
+/// Clockwise rotation applied to camera frames
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rotation {
+    None,
+    Cw90,
+    Cw180,
+    Cw270,
+}
+
+impl Rotation {
+    /// Parse a rotation in degrees (only 0, 90, 180 and 270 are valid)
+    pub fn from_degrees(degrees: u32) -> Option<Rotation> {
+        match degrees {
+            0 => Some(Rotation::None),
+            90 => Some(Rotation::Cw90),
+            180 => Some(Rotation::Cw180),
+            270 => Some(Rotation::Cw270),
+            _ => None,
+        }
+    }
+
+    pub fn degrees(&self) -> u32 {
+        match self {
+            Rotation::None => 0,
+            Rotation::Cw90 => 90,
+            Rotation::Cw180 => 180,
+            Rotation::Cw270 => 270,
+        }
+    }
+}
+
+/// How the camera module is mounted in the enclosure.
+/// Applied in order: lens correction (sensor space), flips, then rotation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameTransform {
+    pub rotation: Rotation,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    /// Radial distortion coefficient of the lens (negative = barrel, positive = pincushion, 0 = off)
+    pub lens_k1: f32,
+}
+
+impl Default for FrameTransform {
+    fn default() -> Self {
+        Self {
+            rotation: Rotation::None,
+            flip_horizontal: false,
+            flip_vertical: false,
+            lens_k1: 0.0,
+        }
+    }
+}
+
+impl FrameTransform {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply the mounting correction to a frame
+    pub fn apply(&self, frame: RgbImage, lens_cache: &mut LensCorrectionCache) -> RgbImage {
+        if self.is_identity() {
+            return frame;
+        }
+
+        let mut img = if self.lens_k1 != 0.0 {
+            lens_cache.correct(&frame, self.lens_k1)
+        } else {
+            frame
+        };
+
+        if self.flip_horizontal {
+            imageops::flip_horizontal_in_place(&mut img);
+        }
+        if self.flip_vertical {
+            imageops::flip_vertical_in_place(&mut img);
+        }
+
+        match self.rotation {
+            Rotation::None => img,
+            Rotation::Cw90 => imageops::rotate90(&img),
+            Rotation::Cw180 => {
+                imageops::rotate180_in_place(&mut img);
+                img
+            }
+            Rotation::Cw270 => imageops::rotate270(&img),
+        }
+    }
+}
+
+/// Precomputed pixel remap for lens correction, rebuilt only when size or coefficient change.
+/// Streaming at 30 FPS makes per-frame trigonometry too slow on the Pi.
+#[derive(Default)]
+pub struct LensCorrectionCache {
+    key: Option<(u32, u32, u32)>,
+    /// Source pixel index for each destination pixel (None = outside the frame)
+    map: Vec<Option<u32>>,
+}
+
+impl LensCorrectionCache {
+    fn correct(&mut self, frame: &RgbImage, k1: f32) -> RgbImage {
+        let (width, height) = frame.dimensions();
+        let key = (width, height, k1.to_bits());
+        if self.key != Some(key) {
+            self.map = build_lens_map(width, height, k1);
+            self.key = Some(key);
+        }
+
+        let src = frame.as_raw();
+        let mut out = RgbImage::new(width, height);
+        for (dst, src_index) in out.chunks_exact_mut(3).zip(&self.map) {
+            if let Some(i) = src_index {
+                let i = *i as usize * 3;
+                dst.copy_from_slice(&src[i..i + 3]);
+            }
+        }
+        out
+    }
+}
+
+/// For each undistorted output pixel, find where it lies in the distorted input:
+/// r_src = r_dst * (1 + k1 * r_dst^2), with r normalised to the half-diagonal.
+fn build_lens_map(width: u32, height: u32, k1: f32) -> Vec<Option<u32>> {
+    let cx = (width as f32 - 1.0) / 2.0;
+    let cy = (height as f32 - 1.0) / 2.0;
+    let norm = (cx * cx + cy * cy).sqrt().max(1.0);
+
+    let mut map = Vec::with_capacity((width * height) as usize);
+    for y in 0..height {
+        for x in 0..width {
+            let nx = (x as f32 - cx) / norm;
+            let ny = (y as f32 - cy) / norm;
+            let scale = 1.0 + k1 * (nx * nx + ny * ny);
+            let sx = (cx + nx * scale * norm).round();
+            let sy = (cy + ny * scale * norm).round();
+
+            if sx >= 0.0 && sy >= 0.0 && sx < width as f32 && sy < height as f32 {
+                map.push(Some(sy as u32 * width + sx as u32));
+            } else {
+                map.push(None);
+            }
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn marked_frame() -> RgbImage {
+        // 3x2 frame with a red marker in the top-left corner
+        let mut img = RgbImage::new(3, 2);
+        img.put_pixel(0, 0, Rgb([255, 0, 0]));
+        img
+    }
+
+    #[test]
+    fn test_rotation_from_degrees() {
+        assert_eq!(Rotation::from_degrees(270), Some(Rotation::Cw270));
+        assert_eq!(Rotation::from_degrees(45), None);
+    }
+
+    #[test]
+    fn test_rotate_and_flip() {
+        let mut cache = LensCorrectionCache::default();
+
+        let rotated = FrameTransform { rotation: Rotation::Cw90, ..Default::default() }
+            .apply(marked_frame(), &mut cache);
+        assert_eq!(rotated.dimensions(), (2, 3));
+        assert_eq!(rotated.get_pixel(1, 0), &Rgb([255, 0, 0]));
+
+        let flipped = FrameTransform { flip_horizontal: true, flip_vertical: true, ..Default::default() }
+            .apply(marked_frame(), &mut cache);
+        assert_eq!(flipped.get_pixel(2, 1), &Rgb([255, 0, 0]));
+    }
+
+    #[test]
+    fn test_lens_correction_keeps_centre() {
+        let mut img = RgbImage::new(5, 5);
+        img.put_pixel(2, 2, Rgb([0, 255, 0]));
+
+        let corrected = FrameTransform { lens_k1: -0.3, ..Default::default() }
+            .apply(img, &mut LensCorrectionCache::default());
+        assert_eq!(corrected.get_pixel(2, 2), &Rgb([0, 255, 0]));
+    }
+}
@@ -0,0 +1,325 @@
+// Fuel-gauge drivers for the UPS monitor
+// Each driver reads voltage and, where the chip reports them, state of charge and charging state
+
+use super::ups_monitor::UpsConfig;
+
+/// One sample from a fuel gauge
+#[derive(Clone, Debug, Default)]
+pub struct GaugeReading {
+    pub voltage: f32,
+    /// State of charge reported by the chip (None = estimate from voltage)
+    pub percentage: Option<f32>,
+    /// Charging state reported by the chip (None = unknown)
+    pub is_charging: Option<bool>,
+}
+
+/// A battery fuel gauge the UPS monitor can poll
+pub trait FuelGauge: Send {
+    fn name(&self) -> &'static str;
+    fn read(&mut self) -> Result<GaugeReading, String>;
+}
+
+/// Fuel-gauge chip selected by the `chip` key in the [ups] section of pixelsort.toml
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GaugeChip {
+    /// Try sysfs, then guess the I2C chip from its address
+    Auto,
+    Ina219,
+    /// MAX17043 single-cell gauge (no charge-rate register)
+    Max17043,
+    /// MAX17048 single-cell gauge
+    Max17048,
+    /// MAX17049 two-cell gauge (same registers, double VCELL scale)
+    Max17049,
+    Sysfs,
+    Fake,
+}
+
+impl GaugeChip {
+    pub fn from_name(name: &str) -> Option<GaugeChip> {
+        match name.to_lowercase().as_str() {
+            "auto" => Some(GaugeChip::Auto),
+            "ina219" => Some(GaugeChip::Ina219),
+            "max17043" => Some(GaugeChip::Max17043),
+            "max17048" => Some(GaugeChip::Max17048),
+            "max17049" => Some(GaugeChip::Max17049),
+            "sysfs" => Some(GaugeChip::Sysfs),
+            "fake" => Some(GaugeChip::Fake),
+            _ => None,
+        }
+    }
+}
+
+/// Create the driver for the configured chip
+pub fn create_gauge(config: &UpsConfig) -> Box<dyn FuelGauge> {
+    match config.chip {
+        // Development machines have no gauge; show a plausible fake battery
+        GaugeChip::Auto if cfg!(not(target_os = "linux")) => Box::new(FakeGauge::new(7.8, false)),
+        GaugeChip::Auto => {
+            if SysfsGauge::detect().is_some() {
+                Box::new(SysfsGauge::new())
+            } else if config.i2c_address == 0x36 {
+                Box::new(Max1704xGauge::new(config.i2c_bus, config.i2c_address, GaugeChip::Max17048))
+            } else {
+                Box::new(Ina219Gauge::new(config.i2c_bus, config.i2c_address))
+            }
+        }
+        GaugeChip::Ina219 => Box::new(Ina219Gauge::new(config.i2c_bus, config.i2c_address)),
+        GaugeChip::Max17043 | GaugeChip::Max17048 | GaugeChip::Max17049 => {
+            Box::new(Max1704xGauge::new(config.i2c_bus, config.i2c_address, config.chip))
+        }
+        GaugeChip::Sysfs => Box::new(SysfsGauge::new()),
+        GaugeChip::Fake => Box::new(FakeGauge::new(config.fake_voltage, config.fake_charging)),
+    }
+}
+
+/// Read a big-endian 16-bit register over SMBus
+#[cfg(target_os = "linux")]
+fn read_register_u16(bus: u8, address: u8, register: u8) -> Result<u16, String> {
+    use i2cdev::core::I2CDevice;
+    use i2cdev::linux::LinuxI2CDevice;
+
+    let i2c_path = format!("/dev/i2c-{}", bus);
+    let mut dev = LinuxI2CDevice::new(&i2c_path, address as u16)
+        .map_err(|e| format!("Cannot open {} address 0x{:02X}: {}", i2c_path, address, e))?;
+
+    let buf = dev
+        .smbus_read_i2c_block_data(register, 2)
+        .map_err(|e| format!("I2C read of register 0x{:02X} failed: {}", register, e))?;
+    if buf.len() < 2 {
+        return Err(format!("Short I2C read of register 0x{:02X}", register));
+    }
+    Ok(((buf[0] as u16) << 8) | (buf[1] as u16))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_register_u16(_bus: u8, _address: u8, _register: u8) -> Result<u16, String> {
+    Err("I2C only available on Linux".to_string())
+}
+
+// ============================================================================
+// INA219 current/voltage monitor (common on 0x40-0x45 UPS HATs)
+// ============================================================================
+
+pub struct Ina219Gauge {
+    bus: u8,
+    address: u8,
+}
+
+impl Ina219Gauge {
+    pub fn new(bus: u8, address: u8) -> Self {
+        Self { bus, address }
+    }
+}
+
+/// INA219 bus voltage register: bits 15..3 in 4 mV steps
+fn ina219_bus_voltage(raw: u16) -> f32 {
+    ((raw >> 3) as f32) * 0.004
+}
+
+impl FuelGauge for Ina219Gauge {
+    fn name(&self) -> &'static str {
+        "INA219"
+    }
+
+    fn read(&mut self) -> Result<GaugeReading, String> {
+        // Register 0x02: Bus Voltage Register
+        let voltage = ina219_bus_voltage(read_register_u16(self.bus, self.address, 0x02)?);
+
+        if voltage <= 0.0 || voltage >= 20.0 {
+            return Err(format!("INA219 voltage out of range: {:.2}V", voltage));
+        }
+
+        Ok(GaugeReading {
+            voltage,
+            percentage: None,
+            // Voltage alone can't tell; the monitor uses the power source instead
+            is_charging: None,
+        })
+    }
+}
+
+// ============================================================================
+// MAX17043/MAX17048/MAX17049 ModelGauge fuel gauges (usually at 0x36)
+// ============================================================================
+
+pub struct Max1704xGauge {
+    bus: u8,
+    address: u8,
+    chip: GaugeChip,
+    /// Whether the chip has a CRATE register, from its VERSION (None = not read yet)
+    has_crate: Option<bool>,
+}
+
+impl Max1704xGauge {
+    pub fn new(bus: u8, address: u8, chip: GaugeChip) -> Self {
+        // The MAX17043 has no CRATE register, so there is nothing to check
+        let has_crate = (chip == GaugeChip::Max17043).then_some(false);
+        Self { bus, address, chip, has_crate }
+    }
+}
+
+/// VCELL register: 78.125 µV per LSB per cell (MAX17043 uses the top 12 bits, which works out the same)
+fn max1704x_vcell_volts(raw: u16, cells: u32) -> f32 {
+    raw as f32 * 78.125e-6 * cells as f32
+}
+
+/// VERSION register: MAX17043 reports 0x000X, MAX17048/49 report 0x001X
+fn max1704x_version_has_crate(version: u16) -> bool {
+    version >= 0x0010
+}
+
+/// SOC register: high byte is whole percent, low byte is 1/256 percent
+fn max1704x_soc_percent(raw: u16) -> f32 {
+    (raw as f32 / 256.0).min(100.0)
+}
+
+impl FuelGauge for Max1704xGauge {
+    fn name(&self) -> &'static str {
+        match self.chip {
+            GaugeChip::Max17043 => "MAX17043",
+            GaugeChip::Max17049 => "MAX17049",
+            _ => "MAX17048",
+        }
+    }
+
+    fn read(&mut self) -> Result<GaugeReading, String> {
+        let cells = if self.chip == GaugeChip::Max17049 { 2 } else { 1 };
+
+        // Register 0x02: VCELL, register 0x04: SOC
+        let voltage = max1704x_vcell_volts(read_register_u16(self.bus, self.address, 0x02)?, cells);
+        let percentage = max1704x_soc_percent(read_register_u16(self.bus, self.address, 0x04)?);
+
+        // Register 0x08: VERSION - a MAX17043 can sit at the same address as a MAX17048
+        if self.has_crate.is_none() {
+            self.has_crate = read_register_u16(self.bus, self.address, 0x08)
+                .ok()
+                .map(max1704x_version_has_crate);
+        }
+
+        // Register 0x16: CRATE (signed, 0.208 %/h per LSB) - MAX17048/49 only, absent on MAX17043
+        let is_charging = if self.has_crate == Some(true) {
+            read_register_u16(self.bus, self.address, 0x16)
+                .ok()
+                .map(|raw| (raw as i16) > 0)
+        } else {
+            None
+        };
+
+        Ok(GaugeReading {
+            voltage,
+            percentage: Some(percentage),
+            is_charging,
+        })
+    }
+}
+
+// ============================================================================
+// Linux power_supply class (UPS HATs with a kernel driver)
+// ============================================================================
+
+pub struct SysfsGauge;
+
+impl SysfsGauge {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Find the first battery-type power supply that reports a voltage
+    fn detect() -> Option<std::path::PathBuf> {
+        let entries = std::fs::read_dir("/sys/class/power_supply").ok()?;
+        entries
+            .flatten()
+            .map(|entry| entry.path())
+            .find(|path| {
+                let supply_type = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+                matches!(supply_type.trim(), "Battery" | "UPS") && path.join("voltage_now").exists()
+            })
+    }
+}
+
+impl FuelGauge for SysfsGauge {
+    fn name(&self) -> &'static str {
+        "sysfs power_supply"
+    }
+
+    fn read(&mut self) -> Result<GaugeReading, String> {
+        let supply = Self::detect().ok_or("No battery found in /sys/class/power_supply")?;
+        let read = |file: &str| std::fs::read_to_string(supply.join(file)).ok().map(|s| s.trim().to_string());
+
+        let microvolts: u64 = read("voltage_now")
+            .and_then(|v| v.parse().ok())
+            .ok_or("Unreadable voltage_now")?;
+
+        Ok(GaugeReading {
+            voltage: microvolts as f32 / 1_000_000.0,
+            percentage: read("capacity").and_then(|v| v.parse().ok()),
+            is_charging: read("status").map(|s| s == "Charging" || s == "Full"),
+        })
+    }
+}
+
+// ============================================================================
+// Fake gauge for development machines and testing the UI
+// ============================================================================
+
+pub struct FakeGauge {
+    voltage: f32,
+    is_charging: bool,
+}
+
+impl FakeGauge {
+    pub fn new(voltage: f32, is_charging: bool) -> Self {
+        Self { voltage, is_charging }
+    }
+}
+
+impl FuelGauge for FakeGauge {
+    fn name(&self) -> &'static str {
+        "fake"
+    }
+
+    fn read(&mut self) -> Result<GaugeReading, String> {
+        Ok(GaugeReading {
+            voltage: self.voltage,
+            percentage: None,
+            is_charging: Some(self.is_charging),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chip_names() {
+        assert_eq!(GaugeChip::from_name("MAX17043"), Some(GaugeChip::Max17043));
+        assert_eq!(GaugeChip::from_name("max17048"), Some(GaugeChip::Max17048));
+        assert_eq!(GaugeChip::from_name("ina219"), Some(GaugeChip::Ina219));
+        assert_eq!(GaugeChip::from_name("bq27441"), None);
+    }
+
+    #[test]
+    fn test_register_conversions() {
+        // 0xD000 = 53248 * 78.125 µV = 4.16 V
+        assert!((max1704x_vcell_volts(0xD000, 1) - 4.16).abs() < 0.001);
+        assert!((max1704x_vcell_volts(0xD000, 2) - 8.32).abs() < 0.001);
+        // 0x3280 = 50.5 %
+        assert!((max1704x_soc_percent(0x3280) - 50.5).abs() < 0.001);
+        // MAX17043 parts report VERSION 0x0002/0x0003, MAX17048 0x0011/0x0012
+        assert!(!max1704x_version_has_crate(0x0003));
+        assert!(max1704x_version_has_crate(0x0012));
+        // 7.4 V = 1850 * 4 mV, shifted into bits 15..3
+        assert!((ina219_bus_voltage(1850 << 3) - 7.4).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_fake_gauge() {
+        let mut gauge = FakeGauge::new(7.8, true);
+        let reading = gauge.read().unwrap();
+        assert_eq!(reading.voltage, 7.8);
+        assert_eq!(reading.percentage, None);
+        assert_eq!(reading.is_charging, Some(true));
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{anyhow, Result};
+use image::RgbImage;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Tethered DSLR/mirrorless camera driven through the gphoto2 command line tool
+#[derive(Clone)]
+pub struct GphotoCamera {
+    /// gphoto2 executable (a fake script can be substituted for testing)
+    binary: String,
+    /// Temporary file paths
+    temp_capture_path: PathBuf,
+    temp_preview_path: PathBuf,
+}
+
+impl GphotoCamera {
+    pub fn new(binary: &str) -> Self {
+        let temp_dir = std::env::temp_dir();
+        Self {
+            binary: binary.to_string(),
+            temp_capture_path: temp_dir.join("pixelsort_gphoto_capture.jpg"),
+            temp_preview_path: temp_dir.join("pixelsort_gphoto_preview.jpg"),
+        }
+    }
+
+    /// gphoto2 executable in use
+    pub fn binary(&self) -> &str {
+        &self.binary
+    }
+
+    /// Check that gphoto2 runs and reports at least one connected camera
+    pub fn detect(&self) -> bool {
+        match Command::new(&self.binary).arg("--auto-detect").output() {
+            Ok(output) if output.status.success() => {
+                let listing = String::from_utf8_lossy(&output.stdout);
+                // Output is a "Model / Port" header, a dashed separator, then one line per camera
+                let camera = listing
+                    .lines()
+                    .skip_while(|line| !line.starts_with("---"))
+                    .nth(1)
+                    .map(|line| line.trim().to_string())
+                    .filter(|line| !line.is_empty());
+
+                match camera {
+                    Some(camera) => {
+                        log::info!("gphoto2 camera detected: {}", camera);
+                        true
+                    }
+                    None => {
+                        log::error!("gphoto2 found no connected camera");
+                        false
+                    }
+                }
+            }
+            Ok(output) => {
+                log::error!("gphoto2 --auto-detect failed: {}", String::from_utf8_lossy(&output.stderr));
+                false
+            }
+            Err(e) => {
+                log::error!("gphoto2 not available ({}): {}", self.binary, e);
+                false
+            }
+        }
+    }
+
+    /// Trigger the shutter and download the full-resolution photo
+    pub fn capture_image(&self) -> Result<RgbImage> {
+        self.run_to_file("--capture-image-and-download", &self.temp_capture_path)
+    }
+
+    /// Grab a live-view frame (low resolution, no shutter actuation)
+    pub fn capture_preview(&self) -> Result<RgbImage> {
+        self.run_to_file("--capture-preview", &self.temp_preview_path)
+    }
+
+    fn run_to_file(&self, action: &str, path: &PathBuf) -> Result<RgbImage> {
+        let _ = std::fs::remove_file(path);
+
+        let output = Command::new(&self.binary)
+            .arg(action)
+            .arg("--filename")
+            .arg(path)
+            .arg("--force-overwrite")
+            .output()
+            .map_err(|e| anyhow!("Command execution failed: {}", e))?;
+
+        if !output.status.success() {
+            return Err(anyhow!(
+                "gphoto2 {} failed: {}",
+                action,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let img = image::open(path).map_err(|e| anyhow!("Failed to load gphoto2 image: {}", e))?;
+        let _ = std::fs::remove_file(path);
+        Ok(img.to_rgb8())
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    /// Write a fake gphoto2 that reports one camera and copies a fixture image to --filename
+    fn fake_gphoto2(fixture: &RgbImage) -> (tempfile::TempDir, GphotoCamera) {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let fixture_path = dir.join("fixture.png");
+        fixture.save(&fixture_path).unwrap();
+
+        let script_path = dir.join("gphoto2");
+        let script = format!(
+            r#"#!/bin/sh
+out=""
+while [ $# -gt 0 ]; do
+  case "$1" in
+    --auto-detect)
+      echo "Model                          Port"
+      echo "----------------------------------------------------------"
+      echo "Fake DSLR                      usb:001,004"
+      exit 0 ;;
+    --filename) out="$2"; shift ;;
+  esac
+  shift
+done
+cp "{}" "$out"
+"#,
+            fixture_path.display()
+        );
+        std::fs::write(&script_path, script).unwrap();
+        std::fs::set_permissions(&script_path, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut camera = GphotoCamera::new(script_path.to_str().unwrap());
+        camera.temp_capture_path = dir.join("capture.png");
+        camera.temp_preview_path = dir.join("preview.png");
+        (temp, camera)
+    }
+
+    #[test]
+    fn test_fake_gphoto2_capture_and_preview() {
+        let fixture = RgbImage::from_pixel(8, 6, image::Rgb([200, 100, 50]));
+        let (_dir, camera) = fake_gphoto2(&fixture);
+
+        assert!(camera.detect());
+        assert_eq!(camera.capture_image().unwrap(), fixture);
+        assert_eq!(camera.capture_preview().unwrap().dimensions(), (8, 6));
+    }
+
+    #[test]
+    fn test_missing_binary_is_not_detected() {
+        let camera = GphotoCamera::new("/nonexistent/gphoto2");
+        assert!(!camera.detect());
+        assert!(camera.capture_image().is_err());
+    }
+}
@@ -1,8 +1,21 @@
 // Hardware domain - interfaces to physical hardware components
 
 pub mod camera_controller;
+pub mod camera_orientation;
+pub mod gphoto_camera;
 pub mod ups_monitor;
+pub mod fuel_gauge;
+pub mod battery_profile;
+pub mod battery_history;
+pub mod power_source;
+pub mod storage_devices;
 
 // Re-export commonly used types
-pub use camera_controller::CameraController;
-pub use ups_monitor::{UpsConfig, get_battery_status, is_shutdown_requested, start_monitoring};
+pub use camera_controller::{CameraBackendKind, CameraController, CameraConfig, CaptureMode};
+pub use camera_orientation::Rotation;
+pub use fuel_gauge::GaugeChip;
+pub use battery_profile::DischargeCurve;
+pub use battery_history::format_runtime;
+pub use power_source::{PowerEvent, PowerSource};
+pub use storage_devices::{RemovableDrive, StorageEvent, removable_drives, start_storage_monitor, take_storage_events};
+pub use ups_monitor::{UpsConfig, flush_battery_history, get_battery_history, get_battery_status, is_shutdown_requested, low_battery_countdown, start_monitoring, take_power_events, take_shutdown_error, update_monitoring};
@@ -0,0 +1,154 @@
+// External power detection for the UPS monitor
+// Reads mains/USB-C adapters from sysfs power_supply, or a "power good" GPIO on the UPS HAT
+
+use std::path::Path;
+
+/// Where the Pi is drawing power from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerSource {
+    /// Mains adapter or USB-C supply connected
+    External,
+    /// Running from the UPS battery
+    Battery,
+    /// No way to tell on this hardware
+    Unknown,
+}
+
+/// Transition between power sources, reported once to the UI
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerEvent {
+    Lost,
+    Restored,
+}
+
+impl PowerEvent {
+    /// Event for a change of source (None when nothing changed or the state is unknown)
+    pub fn from_transition(previous: PowerSource, current: PowerSource) -> Option<PowerEvent> {
+        match (previous, current) {
+            (PowerSource::External, PowerSource::Battery) => Some(PowerEvent::Lost),
+            (PowerSource::Battery, PowerSource::External) => Some(PowerEvent::Restored),
+            // Starting up on battery is worth telling the user about too
+            (PowerSource::Unknown, PowerSource::Battery) => Some(PowerEvent::Lost),
+            _ => None,
+        }
+    }
+}
+
+/// Detects external power using a UPS GPIO when configured, otherwise sysfs
+pub struct PowerDetector {
+    /// BCM pin number and whether the pin reads low while external power is present
+    gpio: Option<(u8, bool)>,
+}
+
+impl PowerDetector {
+    pub fn new(gpio_pin: Option<u8>, active_low: bool) -> Self {
+        Self {
+            gpio: gpio_pin.map(|pin| (pin, active_low)),
+        }
+    }
+
+    pub fn detect(&self) -> PowerSource {
+        let external = match self.gpio {
+            Some((pin, active_low)) => read_gpio(pin).map(|level| level != active_low),
+            None => sysfs_external_online(Path::new("/sys/class/power_supply")),
+        };
+
+        match external {
+            Some(true) => PowerSource::External,
+            Some(false) => PowerSource::Battery,
+            None => PowerSource::Unknown,
+        }
+    }
+}
+
+/// Check every Mains/USB supply's `online` file (None if there are none)
+fn sysfs_external_online(power_supply_dir: &Path) -> Option<bool> {
+    let entries = std::fs::read_dir(power_supply_dir).ok()?;
+
+    let mut found = false;
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let supply_type = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        if !matches!(supply_type.trim(), "Mains" | "USB" | "USB_C" | "USB_PD") {
+            continue;
+        }
+
+        if let Ok(online) = std::fs::read_to_string(path.join("online")) {
+            found = true;
+            if online.trim() == "1" {
+                return Some(true);
+            }
+        }
+    }
+
+    found.then_some(false)
+}
+
+/// Read a GPIO input level with the Pi's `pinctrl` tool ("6: ip pu | hi // GPIO6 = input")
+#[cfg(target_os = "linux")]
+fn read_gpio(pin: u8) -> Option<bool> {
+    let output = std::process::Command::new("pinctrl")
+        .args(["get", &pin.to_string()])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_pinctrl_level(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_gpio(_pin: u8) -> Option<bool> {
+    None
+}
+
+fn parse_pinctrl_level(output: &str) -> Option<bool> {
+    let level = output.split('|').nth(1)?.split_whitespace().next()?;
+    match level {
+        "hi" => Some(true),
+        "lo" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        use PowerSource::*;
+        assert_eq!(PowerEvent::from_transition(External, Battery), Some(PowerEvent::Lost));
+        assert_eq!(PowerEvent::from_transition(Battery, External), Some(PowerEvent::Restored));
+        assert_eq!(PowerEvent::from_transition(Unknown, External), None);
+        assert_eq!(PowerEvent::from_transition(External, Unknown), None);
+    }
+
+    #[test]
+    fn test_sysfs_online() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let write_supply = |name: &str, supply_type: &str, online: &str| {
+            let supply = dir.join(name);
+            std::fs::create_dir_all(&supply).unwrap();
+            std::fs::write(supply.join("type"), supply_type).unwrap();
+            std::fs::write(supply.join("online"), online).unwrap();
+        };
+
+        write_supply("battery", "Battery\n", "1\n");
+        assert_eq!(sysfs_external_online(dir), None);
+
+        write_supply("usb", "USB\n", "0\n");
+        assert_eq!(sysfs_external_online(dir), Some(false));
+
+        write_supply("ac", "Mains\n", "1\n");
+        assert_eq!(sysfs_external_online(dir), Some(true));
+
+    }
+
+    #[test]
+    fn test_pinctrl_parsing() {
+        assert_eq!(parse_pinctrl_level("6: ip pu | hi // GPIO6 = input\n"), Some(true));
+        assert_eq!(parse_pinctrl_level("6: ip pd | lo // GPIO6 = input\n"), Some(false));
+        assert_eq!(parse_pinctrl_level("garbage"), None);
+    }
+}
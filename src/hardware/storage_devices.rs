@@ -0,0 +1,261 @@
+// Removable drive detection for USB export
+// Polls /proc/self/mountinfo off the UI thread and uses sysfs to tell USB/SD drives
+// apart from the Pi's own storage, reporting insertions and removals to the UI
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::sleep;
+
+const MOUNTINFO_PATH: &str = "/proc/self/mountinfo";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Mount points that belong to the system even if the disk looks removable (e.g. USB boot)
+const SYSTEM_MOUNTS: &[&str] = &["/", "/boot", "/boot/firmware", "/home", "/var", "/usr"];
+/// Where desktop automounters and fstab entries put removable drives
+const REMOVABLE_MOUNT_ROOTS: &[&str] = &["/media/", "/run/media/", "/mnt/"];
+
+lazy_static::lazy_static! {
+    static ref DRIVES: Mutex<Vec<RemovableDrive>> = Mutex::new(Vec::new());
+    // Insertions and removals not yet shown by the UI
+    static ref STORAGE_EVENTS: Mutex<Vec<StorageEvent>> = Mutex::new(Vec::new());
+}
+
+/// A mounted removable drive
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemovableDrive {
+    /// Block device, e.g. /dev/sda1
+    pub device: String,
+    pub mount_point: PathBuf,
+    /// Filesystem as reported by the kernel (vfat, exfat, ext4, fuseblk for NTFS, ...)
+    pub fs_type: String,
+    pub label: String,
+    pub size_bytes: Option<u64>,
+    pub writable: bool,
+}
+
+impl RemovableDrive {
+    /// Short description for the drive picker, e.g. "PHOTOS (exfat, 31.9 GB)"
+    pub fn description(&self) -> String {
+        match self.size_bytes {
+            Some(bytes) => format!("{} ({}, {:.1} GB)", self.label, self.fs_type, bytes as f64 / 1e9),
+            None => format!("{} ({})", self.label, self.fs_type),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StorageEvent {
+    Added(RemovableDrive),
+    Removed(RemovableDrive),
+}
+
+/// One line of /proc/self/mountinfo
+#[derive(Debug, Clone, PartialEq)]
+struct MountEntry {
+    /// "major:minor" of the block device
+    device_number: String,
+    mount_point: String,
+    read_only: bool,
+    fs_type: String,
+    source: String,
+}
+
+/// Start watching for removable drives in the background
+pub fn start_storage_monitor() {
+    tokio::spawn(async {
+        log::info!("Removable drive monitor started");
+        loop {
+            let drives = scan_drives();
+            if let Ok(mut current) = DRIVES.lock() {
+                let events = drive_events(&current, &drives);
+                for event in &events {
+                    match event {
+                        StorageEvent::Added(drive) => log::info!("Drive attached: {} at {}", drive.device, drive.mount_point.display()),
+                        StorageEvent::Removed(drive) => log::info!("Drive removed: {} from {}", drive.device, drive.mount_point.display()),
+                    }
+                }
+                if !events.is_empty() {
+                    if let Ok(mut pending) = STORAGE_EVENTS.lock() {
+                        pending.extend(events);
+                    }
+                }
+                *current = drives;
+            }
+            sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+/// Currently mounted removable drives
+pub fn removable_drives() -> Vec<RemovableDrive> {
+    DRIVES.lock().map(|drives| drives.clone()).unwrap_or_default()
+}
+
+/// Drain drive insertions/removals since the last call
+pub fn take_storage_events() -> Vec<StorageEvent> {
+    STORAGE_EVENTS.lock().map(|mut events| std::mem::take(&mut *events)).unwrap_or_default()
+}
+
+fn scan_drives() -> Vec<RemovableDrive> {
+    let contents = std::fs::read_to_string(MOUNTINFO_PATH).unwrap_or_default();
+    parse_mountinfo(&contents)
+        .into_iter()
+        .filter(|entry| entry.source.starts_with("/dev/") && !SYSTEM_MOUNTS.contains(&entry.mount_point.as_str()))
+        .filter(|entry| is_removable(&entry.device_number, &entry.mount_point))
+        .map(|entry| {
+            let mount_point = PathBuf::from(&entry.mount_point);
+            let label = mount_point
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| entry.source.clone());
+            RemovableDrive {
+                size_bytes: block_device_size(&entry.device_number),
+                device: entry.source,
+                mount_point,
+                fs_type: entry.fs_type,
+                label,
+                writable: !entry.read_only,
+            }
+        })
+        .collect()
+}
+
+/// USB or SD-reader attached disk, judged from sysfs; falls back to the mount location
+fn is_removable(device_number: &str, mount_point: &str) -> bool {
+    let sysfs = Path::new("/sys/dev/block").join(device_number);
+    if let Ok(device_path) = std::fs::canonicalize(&sysfs) {
+        if device_path.to_string_lossy().contains("/usb") {
+            return true;
+        }
+        // Partitions keep the "removable" flag on their parent disk
+        for dir in [device_path.as_path(), device_path.parent().unwrap_or(&device_path)] {
+            if let Ok(flag) = std::fs::read_to_string(dir.join("removable")) {
+                return flag.trim() == "1";
+            }
+        }
+    }
+
+    REMOVABLE_MOUNT_ROOTS.iter().any(|root| mount_point.starts_with(root))
+}
+
+fn block_device_size(device_number: &str) -> Option<u64> {
+    let sectors: u64 = std::fs::read_to_string(Path::new("/sys/dev/block").join(device_number).join("size"))
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    // sysfs always counts 512-byte sectors
+    Some(sectors * 512).filter(|&bytes| bytes > 0)
+}
+
+/// Parse mountinfo lines:
+/// "36 25 8:1 / /media/pi/USB rw,nosuid,relatime shared:1 - vfat /dev/sda1 rw,uid=1000"
+fn parse_mountinfo(contents: &str) -> Vec<MountEntry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (mount, filesystem) = line.split_once(" - ")?;
+            let mount: Vec<&str> = mount.split_whitespace().collect();
+            let mut filesystem = filesystem.split_whitespace();
+            Some(MountEntry {
+                device_number: mount.get(2)?.to_string(),
+                mount_point: unescape_octal(mount.get(4)?),
+                read_only: mount.get(5)?.split(',').any(|option| option == "ro"),
+                fs_type: filesystem.next()?.to_string(),
+                source: unescape_octal(filesystem.next()?),
+            })
+        })
+        .collect()
+}
+
+/// The kernel writes spaces, tabs and backslashes in paths as \040, \011 and \134
+fn unescape_octal(field: &str) -> String {
+    let mut result = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(index) = rest.find('\\') {
+        result.push_str(&rest[..index]);
+        let code = rest.get(index + 1..index + 4).and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match code {
+            Some(byte) => {
+                result.push(byte as char);
+                rest = &rest[index + 4..];
+            }
+            None => {
+                result.push('\\');
+                rest = &rest[index + 1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// Drives that appeared or disappeared between two scans
+fn drive_events(previous: &[RemovableDrive], current: &[RemovableDrive]) -> Vec<StorageEvent> {
+    let same = |a: &RemovableDrive, b: &RemovableDrive| a.device == b.device && a.mount_point == b.mount_point;
+
+    let removed = previous
+        .iter()
+        .filter(|old| !current.iter().any(|new| same(old, new)))
+        .map(|drive| StorageEvent::Removed(drive.clone()));
+    let added = current
+        .iter()
+        .filter(|new| !previous.iter().any(|old| same(old, new)))
+        .map(|drive| StorageEvent::Added(drive.clone()));
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mountinfo() {
+        let contents = "\
+22 1 179:2 / / rw,noatime shared:1 - ext4 /dev/mmcblk0p2 rw
+25 22 0:21 / /proc rw,nosuid shared:12 - proc proc rw
+36 22 8:1 / /media/pi/MY\\040PHOTOS rw,nosuid,nodev,relatime shared:2 - exfat /dev/sda1 rw,uid=1000
+37 22 8:17 / /media/pi/BACKUP ro,relatime shared:3 master:1 - ext4 /dev/sdb1 ro
+";
+        let entries = parse_mountinfo(contents);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[2].mount_point, "/media/pi/MY PHOTOS");
+        assert_eq!(entries[2].fs_type, "exfat");
+        assert_eq!(entries[2].source, "/dev/sda1");
+        assert_eq!(entries[2].device_number, "8:1");
+        assert!(!entries[2].read_only);
+        assert_eq!(entries[3].fs_type, "ext4");
+        assert!(entries[3].read_only);
+    }
+
+    #[test]
+    fn test_unescape() {
+        assert_eq!(unescape_octal("/media/a\\040b"), "/media/a b");
+        assert_eq!(unescape_octal("/media/tab\\011x\\134"), "/media/tab\tx\\");
+        assert_eq!(unescape_octal("/plain"), "/plain");
+    }
+
+    #[test]
+    fn test_drive_events() {
+        let drive = |device: &str, mount: &str| RemovableDrive {
+            device: device.to_string(),
+            mount_point: PathBuf::from(mount),
+            fs_type: "vfat".to_string(),
+            label: "USB".to_string(),
+            size_bytes: None,
+            writable: true,
+        };
+        let stick = drive("/dev/sda1", "/media/pi/USB");
+        let card = drive("/dev/sdb1", "/media/pi/CARD");
+
+        let (stick, card) = (vec![stick], vec![card]);
+
+        assert_eq!(drive_events(&[], &stick), vec![StorageEvent::Added(stick[0].clone())]);
+        assert_eq!(drive_events(&stick, &stick), vec![]);
+        assert_eq!(
+            drive_events(&stick, &card),
+            vec![StorageEvent::Removed(stick[0].clone()), StorageEvent::Added(card[0].clone())]
+        );
+    }
+}
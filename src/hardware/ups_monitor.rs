@@ -2,16 +2,36 @@
 // Monitors I2C-based UPS HAT for low battery warnings
 
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 
+use super::battery_history::{BatteryHistory, BatterySample};
+use super::battery_profile::{DischargeCurve, SocHysteresis};
+use super::fuel_gauge::{create_gauge, FuelGauge, GaugeChip};
+use super::power_source::{PowerDetector, PowerEvent, PowerSource};
+
 // Shared flag to signal shutdown request
 static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
 
+// Whether the monitoring task is running (so a reloaded config can be handed to it)
+static MONITOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
 // Shared battery status
 lazy_static::lazy_static! {
     static ref BATTERY_STATUS: Arc<Mutex<BatteryStatus>> = Arc::new(Mutex::new(BatteryStatus::default()));
+    // When the low-battery shutdown will happen (None = no countdown running)
+    static ref SHUTDOWN_DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+    // Power source transitions not yet shown by the UI
+    static ref POWER_EVENTS: Mutex<Vec<PowerEvent>> = Mutex::new(Vec::new());
+    // Rolling sample log used for runtime estimates and the developer menu chart
+    // (loaded from the configured file when monitoring starts)
+    static ref BATTERY_HISTORY: Mutex<BatteryHistory> = Mutex::new(BatteryHistory::in_memory());
+    // Reloaded configuration waiting to be picked up by the monitoring task
+    static ref PENDING_CONFIG: Mutex<Option<UpsConfig>> = Mutex::new(None);
+    // Why the last low-battery shutdown could not be started, until the UI shows it
+    static ref SHUTDOWN_ERROR: Mutex<Option<String>> = Mutex::new(None);
 }
 
 /// Battery status information
@@ -21,6 +41,9 @@ pub struct BatteryStatus {
     pub percentage: f32,
     pub is_charging: bool,
     pub is_available: bool,
+    /// Remaining runtime when discharging, time to full when charging (None until there is enough history)
+    pub runtime_estimate: Option<Duration>,
+    pub power_source: PowerSource,
 }
 
 impl Default for BatteryStatus {
@@ -30,6 +53,8 @@ impl Default for BatteryStatus {
             percentage: 0.0,
             is_charging: false,
             is_available: false,
+            runtime_estimate: None,
+            power_source: PowerSource::Unknown,
         }
     }
 }
@@ -39,11 +64,65 @@ pub fn get_battery_status() -> BatteryStatus {
     BATTERY_STATUS.lock().unwrap().clone()
 }
 
+/// Recorded battery samples, oldest first
+pub fn get_battery_history() -> Vec<BatterySample> {
+    BATTERY_HISTORY
+        .lock()
+        .map(|history| history.samples().iter().copied().collect())
+        .unwrap_or_default()
+}
+
+/// Write buffered battery samples to disk, e.g. before the app exits
+pub fn flush_battery_history() {
+    if let Ok(mut history) = BATTERY_HISTORY.lock() {
+        history.flush();
+    }
+}
+
+/// Keep the battery history in `path`, saving the current one first if it moves
+fn use_history_file(path: &Path) {
+    let Ok(mut history) = BATTERY_HISTORY.lock() else {
+        return;
+    };
+    if history.path() != Some(path) {
+        history.flush();
+        *history = BatteryHistory::load(path);
+    }
+}
+
+/// Power source transitions since the last call
+pub fn take_power_events() -> Vec<PowerEvent> {
+    POWER_EVENTS
+        .lock()
+        .map(|mut events| std::mem::take(&mut *events))
+        .unwrap_or_default()
+}
+
 /// Check if shutdown has been requested by UPS
 pub fn is_shutdown_requested() -> bool {
     SHUTDOWN_REQUESTED.load(Ordering::Relaxed)
 }
 
+/// Time left before the low-battery shutdown, while the countdown is running
+pub fn low_battery_countdown() -> Option<Duration> {
+    SHUTDOWN_DEADLINE
+        .lock()
+        .ok()
+        .and_then(|deadline| *deadline)
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+/// Error from a low-battery shutdown that failed to start, once
+pub fn take_shutdown_error() -> Option<String> {
+    SHUTDOWN_ERROR.lock().ok().and_then(|mut error| error.take())
+}
+
+fn set_shutdown_deadline(deadline: Option<Instant>) {
+    if let Ok(mut current) = SHUTDOWN_DEADLINE.lock() {
+        *current = deadline;
+    }
+}
+
 /// UPS Monitor configuration
 #[derive(Clone)]
 pub struct UpsConfig {
@@ -52,6 +131,15 @@ pub struct UpsConfig {
     pub i2c_address: u8,
     pub voltage_threshold: f32,  // Minimum voltage before shutdown
     pub check_interval_secs: u64,
+    pub warning_duration_secs: u64, // Countdown shown before shutting down
+    pub chip: GaugeChip,
+    pub fake_voltage: f32,       // Reported by the fake gauge
+    pub fake_charging: bool,
+    pub power_gpio: Option<u8>,  // BCM pin of the UPS "power good" output (None = use sysfs)
+    pub power_gpio_active_low: bool,
+    pub discharge_curve: DischargeCurve, // Voltage -> percentage when the chip doesn't report it
+    pub soc_hysteresis: f32,     // Percentage points the reading must move before the display changes
+    pub history_file: PathBuf,   // Battery sample log used for runtime estimates
 }
 
 impl Default for UpsConfig {
@@ -62,6 +150,15 @@ impl Default for UpsConfig {
             i2c_address: 0x36, // Common address for fuel gauge ICs
             voltage_threshold: 3.2, // 3.2V per cell (for 2S = 6.4V total)
             check_interval_secs: 5,
+            warning_duration_secs: 30,
+            chip: GaugeChip::Auto,
+            fake_voltage: 7.8,
+            fake_charging: false,
+            power_gpio: None,
+            power_gpio_active_low: false,
+            discharge_curve: DischargeCurve::default(),
+            soc_hysteresis: 2.0,
+            history_file: PathBuf::from("battery_history.csv"),
         }
     }
 }
@@ -76,63 +173,129 @@ pub fn start_monitoring(config: UpsConfig) -> Arc<AtomicBool> {
         return shutdown_flag;
     }
 
+    MONITOR_RUNNING.store(true, Ordering::Relaxed);
+    use_history_file(&config.history_file);
     tokio::spawn(async move {
-        log::info!("UPS monitoring started (I2C bus {}, address 0x{:02X})", 
-                   config.i2c_bus, config.i2c_address);
+        let mut config = config;
+        let mut gauge = create_gauge(&config);
+        let mut hysteresis = SocHysteresis::new(config.soc_hysteresis);
+        let mut power = PowerDetector::new(config.power_gpio, config.power_gpio_active_low);
+        // Set after a failed shutdown so the countdown isn't re-armed until the battery recovers
+        let mut shutdown_failed = false;
+        log::info!("UPS monitoring started ({} gauge, I2C bus {}, address 0x{:02X})", 
+                   gauge.name(), config.i2c_bus, config.i2c_address);
         
         // Do an immediate check first to populate battery status
-        if let Ok((voltage, is_charging)) = check_battery_voltage(&config) {
-            let percentage = voltage_to_percentage(voltage, config.voltage_threshold);
-            if let Ok(mut status) = BATTERY_STATUS.lock() {
-                status.voltage = voltage;
-                status.percentage = percentage;
-                status.is_charging = is_charging;
-                status.is_available = true;
-            }
+        update_power_source(&power);
+        if let Ok((voltage, percentage, is_charging)) = read_battery(gauge.as_mut(), &config, &mut hysteresis) {
             log::info!("Initial battery status: {:.2}V ({:.0}%) {}", 
                       voltage, percentage, 
                       if is_charging { "charging" } else { "discharging" });
         }
         
         loop {
-            sleep(Duration::from_secs(config.check_interval_secs)).await;
-            
-            // Check battery status
-            match check_battery_voltage(&config) {
-                Ok((voltage, is_charging)) => {
-                    // Calculate percentage (rough estimate based on voltage)
-                    let percentage = voltage_to_percentage(voltage, config.voltage_threshold);
-                    
-                    // Update shared battery status
-                    if let Ok(mut status) = BATTERY_STATUS.lock() {
-                        status.voltage = voltage;
-                        status.percentage = percentage;
-                        status.is_charging = is_charging;
-                        status.is_available = true;
+            // Power loss is checked every second so the UI hears about it straight away
+            for _ in 0..config.check_interval_secs.max(1) {
+                sleep(Duration::from_secs(1)).await;
+                update_power_source(&power);
+                
+                // Pick up a reloaded config
+                let pending = PENDING_CONFIG.lock().ok().and_then(|mut pending| pending.take());
+                if let Some(new_config) = pending {
+                    if !new_config.enabled {
+                        log::info!("UPS monitoring disabled by config reload");
+                        MONITOR_RUNNING.store(false, Ordering::Relaxed);
+                        flush_battery_history();
+                        if let Ok(mut status) = BATTERY_STATUS.lock() {
+                            *status = BatteryStatus::default();
+                        }
+                        return;
                     }
                     
+                    let gauge_changed = new_config.chip != config.chip
+                        || new_config.i2c_bus != config.i2c_bus
+                        || new_config.i2c_address != config.i2c_address
+                        || new_config.fake_voltage != config.fake_voltage
+                        || new_config.fake_charging != config.fake_charging;
+                    if gauge_changed {
+                        gauge = create_gauge(&new_config);
+                        log::info!("UPS gauge reconfigured ({})", gauge.name());
+                    }
+                    if new_config.soc_hysteresis != config.soc_hysteresis || new_config.discharge_curve != config.discharge_curve {
+                        hysteresis = SocHysteresis::new(new_config.soc_hysteresis);
+                    }
+                    power = PowerDetector::new(new_config.power_gpio, new_config.power_gpio_active_low);
+                    use_history_file(&new_config.history_file);
+                    config = new_config;
+                    break; // Restart the wait with the new check interval
+                }
+            }
+            
+            // Check battery status
+            match read_battery(gauge.as_mut(), &config, &mut hysteresis) {
+                Ok((voltage, percentage, is_charging)) => {
                     log::debug!("Battery: {:.2}V ({:.0}%) {}", 
                               voltage, percentage, 
                               if is_charging { "charging" } else { "discharging" });
                     
+                    if shutdown_failed {
+                        if is_charging || voltage >= config.voltage_threshold {
+                            log::info!("Battery recovered - low battery shutdown re-armed");
+                            shutdown_failed = false;
+                        }
+                        continue;
+                    }
+                    
                     if voltage < config.voltage_threshold && !is_charging {
                         log::warn!("LOW BATTERY WARNING: {:.2}V (threshold: {:.2}V)", 
                                   voltage, config.voltage_threshold);
                         
-                        // Give user 30 seconds warning
-                        for i in (1..=6).rev() {
-                            log::warn!("Shutting down in {} seconds...", i * 5);
-                            sleep(Duration::from_secs(5)).await;
+                        // Give the user a warning; the UI shows the countdown and saves their work
+                        let warning = Duration::from_secs(config.warning_duration_secs);
+                        set_shutdown_deadline(Some(Instant::now() + warning));
+                        
+                        let mut cancelled = false;
+                        for remaining in (1..=config.warning_duration_secs).rev() {
+                            if remaining % 5 == 0 {
+                                log::warn!("Shutting down in {} seconds...", remaining);
+                            }
+                            sleep(Duration::from_secs(1)).await;
+                            update_power_source(&power);
+                            
+                            // Plugging in the charger cancels the shutdown
+                            if let Ok((_, _, true)) = read_battery(gauge.as_mut(), &config, &mut hysteresis) {
+                                cancelled = true;
+                                break;
+                            }
+                        }
+                        // The deadline stays set on the way to shutdown, so the UI keeps blocking
+                        if cancelled {
+                            set_shutdown_deadline(None);
+                            log::info!("Charging resumed - low battery shutdown cancelled");
+                            continue;
                         }
                         
                         log::error!("Battery critical! Initiating safe shutdown...");
-                        SHUTDOWN_REQUESTED.store(true, Ordering::Relaxed);
-                        flag_clone.store(true, Ordering::Relaxed);
+                        flush_battery_history();
                         
                         // Trigger system shutdown
-                        if let Err(e) = initiate_system_shutdown() {
+                        let result = tokio::task::spawn_blocking(initiate_system_shutdown)
+                            .await
+                            .unwrap_or_else(|e| Err(format!("Shutdown task failed: {}", e)));
+                        if let Err(e) = result {
+                            // Keep monitoring so the UI isn't stuck behind a countdown that never ends,
+                            // but leave the user to shut down by hand instead of restarting the countdown
                             log::error!("Failed to initiate system shutdown: {}", e);
+                            set_shutdown_deadline(None);
+                            shutdown_failed = true;
+                            if let Ok(mut error) = SHUTDOWN_ERROR.lock() {
+                                *error = Some(e);
+                            }
+                            continue;
                         }
+                        SHUTDOWN_REQUESTED.store(true, Ordering::Relaxed);
+                        flag_clone.store(true, Ordering::Relaxed);
+                        MONITOR_RUNNING.store(false, Ordering::Relaxed);
                         break;
                     }
                 }
@@ -149,94 +312,97 @@ pub fn start_monitoring(config: UpsConfig) -> Arc<AtomicBool> {
     shutdown_flag
 }
 
-/// Convert voltage to percentage (rough estimation)
-fn voltage_to_percentage(voltage: f32, min_voltage: f32) -> f32 {
-    // For 2S Li-ion (7.4V nominal):
-    // 8.4V = 100%, 6.4V = 0%
-    let max_voltage = 8.4;
-    
-    ((voltage - min_voltage) / (max_voltage - min_voltage) * 100.0).clamp(0.0, 100.0)
+/// Apply a reloaded UPS config: hand it to the running monitor, or start one if it was disabled
+pub fn update_monitoring(config: UpsConfig) {
+    if MONITOR_RUNNING.load(Ordering::Relaxed) {
+        if let Ok(mut pending) = PENDING_CONFIG.lock() {
+            *pending = Some(config);
+        }
+    } else if config.enabled {
+        start_monitoring(config);
+    }
 }
 
-/// Read battery voltage from I2C UPS HAT
-/// Returns (voltage, is_charging)
-#[cfg(target_os = "linux")]
-fn check_battery_voltage(config: &UpsConfig) -> Result<(f32, bool), String> {
-    use std::fs::File;
-    use std::io::Read;
+/// Sample the fuel gauge and publish the result to the shared battery status.
+/// Percentage comes from the chip when it reports one, otherwise from the discharge curve.
+/// Returns (voltage, percentage, is_charging)
+fn read_battery(
+    gauge: &mut dyn FuelGauge,
+    config: &UpsConfig,
+    hysteresis: &mut SocHysteresis,
+) -> Result<(f32, f32, bool), String> {
+    let reading = gauge.read()?;
     
-    let mut is_charging = false;
+    // Chips that can't tell are assumed to be charging whenever external power is present
+    let is_charging = reading.is_charging.unwrap_or_else(|| match get_battery_status().power_source {
+        PowerSource::External => true,
+        PowerSource::Battery => false,
+        PowerSource::Unknown => sysfs_charging_status(),
+    });
+    let raw_percentage = reading
+        .percentage
+        .unwrap_or_else(|| config.discharge_curve.percentage(reading.voltage));
+    let percentage = hysteresis.update(raw_percentage, is_charging);
     
-    // Check if charging from sysfs
-    let status_paths = [
-        "/sys/class/power_supply/battery/status",
-        "/sys/class/power_supply/BAT0/status",
-    ];
+    // Log the raw percentage so the trend isn't flattened by the hysteresis
+    let runtime_estimate = BATTERY_HISTORY.lock().ok().and_then(|mut history| {
+        history.record(BatterySample::now(reading.voltage, raw_percentage, is_charging));
+        history.estimate()
+    });
     
-    for path in &status_paths {
-        if let Ok(mut file) = File::open(path) {
-            let mut contents = String::new();
-            if file.read_to_string(&mut contents).is_ok() {
-                is_charging = contents.trim().to_lowercase().contains("charging");
-                break;
-            }
-        }
+    // Update shared battery status
+    if let Ok(mut status) = BATTERY_STATUS.lock() {
+        status.voltage = reading.voltage;
+        status.percentage = percentage;
+        status.is_charging = is_charging;
+        status.is_available = true;
+        status.runtime_estimate = runtime_estimate;
     }
     
-    // Try to read from sysfs first (some UPS HATs expose this)
-    let sysfs_paths = [
-        "/sys/class/power_supply/battery/voltage_now",
-        "/sys/class/power_supply/BAT0/voltage_now",
-        "/sys/class/power_supply/ups/voltage_now",
-    ];
-    
-    for path in &sysfs_paths {
-        if let Ok(mut file) = File::open(path) {
-            let mut contents = String::new();
-            if file.read_to_string(&mut contents).is_ok() {
-                if let Ok(microvolts) = contents.trim().parse::<u64>() {
-                    let volts = microvolts as f32 / 1_000_000.0;
-                    return Ok((volts, is_charging));
-                }
-            }
+    Ok((reading.voltage, percentage, is_charging))
+}
+
+/// Detect the current power source and queue an event when it changes
+fn update_power_source(detector: &PowerDetector) {
+    let current = detector.detect();
+
+    let previous = match BATTERY_STATUS.lock() {
+        Ok(mut status) => std::mem::replace(&mut status.power_source, current),
+        Err(_) => return,
+    };
+
+    if let Some(event) = PowerEvent::from_transition(previous, current) {
+        match event {
+            PowerEvent::Lost => log::warn!("External power lost - running on battery"),
+            PowerEvent::Restored => log::info!("External power restored"),
+        }
+        if let Ok(mut events) = POWER_EVENTS.lock() {
+            events.push(event);
         }
     }
+}
+
+/// Charging state from sysfs, for gauges that can't report it themselves
+#[cfg(target_os = "linux")]
+fn sysfs_charging_status() -> bool {
+    let status_paths = [
+        "/sys/class/power_supply/battery/status",
+        "/sys/class/power_supply/BAT0/status",
+    ];
     
-    // Read directly from I2C using i2cdev
-    use i2cdev::core::I2CDevice;
-    use i2cdev::linux::LinuxI2CDevice;
-    
-    let i2c_path = format!("/dev/i2c-{}", config.i2c_bus);
-    
-    if let Ok(mut dev) = LinuxI2CDevice::new(&i2c_path, config.i2c_address as u16) {
-        // INA219-style voltage reading (common for 0x42 UPS HATs)
-        // Register 0x02: Bus Voltage Register
-        // Read 2 bytes from register 0x02
-        if let Ok(buf) = dev.smbus_read_i2c_block_data(0x02, 2) {
-            if buf.len() >= 2 {
-                // Combine bytes (big-endian)
-                let raw_value = ((buf[0] as u16) << 8) | (buf[1] as u16);
-                
-                // INA219: Voltage = (raw >> 3) * 4mV
-                let voltage = ((raw_value >> 3) as f32) * 0.004;
-                
-                if voltage > 0.0 && voltage < 20.0 {  // Sanity check
-                    // Estimate charging based on voltage
-                    // 2S Li-ion: > 8.0V usually means charging or full
-                    let estimated_charging = voltage > 8.0;
-                    return Ok((voltage, is_charging || estimated_charging));
-                }
-            }
+    for path in &status_paths {
+        if let Ok(contents) = std::fs::read_to_string(path) {
+            let status = contents.trim();
+            return status == "Charging" || status == "Full";
         }
     }
     
-    Err("Battery voltage unavailable (sysfs and I2C failed)".to_string())
+    false
 }
 
 #[cfg(not(target_os = "linux"))]
-fn check_battery_voltage(_config: &UpsConfig) -> Result<(f32, bool), String> {
-    // On non-Linux (development machines), return safe voltage
-    Ok((7.8, false)) // ~75% battery, not charging
+fn sysfs_charging_status() -> bool {
+    false
 }
 
 /// Initiate safe system shutdown
@@ -246,10 +412,14 @@ fn initiate_system_shutdown() -> Result<(), String> {
     
     log::info!("Executing system shutdown command...");
     
-    Command::new("sudo")
-        .args(&["shutdown", "-h", "now"])
-        .spawn()
+    // -n: fail instead of waiting for a password nobody can type
+    let status = Command::new("sudo")
+        .args(["-n", "shutdown", "-h", "now"])
+        .status()
         .map_err(|e| format!("Failed to execute shutdown: {}", e))?;
+    if !status.success() {
+        return Err(format!("shutdown command failed ({})", status));
+    }
     
     Ok(())
 }
@@ -259,32 +429,3 @@ fn initiate_system_shutdown() -> Result<(), String> {
     log::info!("Shutdown requested (simulated on non-Linux)");
     Ok(())
 }
-
-/// Check if running on battery power (vs AC)
-#[cfg(target_os = "linux")]
-#[allow(dead_code)]
-pub fn is_on_battery() -> bool {
-    use std::fs;
-    
-    // Check common power supply status files
-    let status_paths = [
-        "/sys/class/power_supply/AC/online",
-        "/sys/class/power_supply/ADP0/online",
-    ];
-    
-    for path in &status_paths {
-        if let Ok(contents) = fs::read_to_string(path) {
-            if let Ok(online) = contents.trim().parse::<u8>() {
-                return online == 0; // 0 = on battery, 1 = AC connected
-            }
-        }
-    }
-    
-    false // Assume on AC if unknown
-}
-
-#[cfg(not(target_os = "linux"))]
-#[allow(dead_code)]
-pub fn is_on_battery() -> bool {
-    false // Development machine
-}
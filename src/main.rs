@@ -13,7 +13,8 @@ mod ui;
 
 use crate::processing::PixelSorter;
 use crate::ui::PixelSorterApp;
-use crate::hardware::{CameraController, UpsConfig};
+use crate::hardware::CameraController;
+use crate::system::AppConfig;
 
 #[tokio::main]
 #[allow(clippy::arc_with_non_send_sync)]
@@ -25,17 +26,28 @@ async fn main() -> Result<()> {
     
     info!("Starting Raspberry Pi Pixel Sorter (Rust Edition)");
 
-    // Load UPS configuration
-    let ups_config = load_ups_config();
+    // Load configuration (invalid sections fall back to defaults and are reported in the UI)
+    let (config, config_error) = AppConfig::load_with_fallback();
+    if let Some(e) = &config_error {
+        log::error!("Invalid configuration, affected sections use defaults: {}", e);
+    }
     
     // Start UPS monitoring (runs in background)
-    let _shutdown_flag = hardware::start_monitoring(ups_config);
+    let _shutdown_flag = hardware::start_monitoring(config.ups_config());
+    
+    // Watch for USB drives being attached or removed
+    hardware::start_storage_monitor();
     
     // Initialize components
     let pixel_sorter = Arc::new(PixelSorter::new());
 
     // Initialize Camera controller  
-    let camera_controller = match CameraController::new() {
+    let camera_config = config.camera_config();
+    info!("Camera: {:?} backend, rotation {}°, flip h={} v={}, lens k1={}",
+          camera_config.backend, camera_config.transform.rotation.degrees(),
+          camera_config.transform.flip_horizontal, camera_config.transform.flip_vertical,
+          camera_config.transform.lens_k1);
+    let camera_controller = match CameraController::new(camera_config) {
         Ok(controller) => {
             Some(Arc::new(RwLock::new(controller)))
         }
@@ -51,8 +63,8 @@ async fn main() -> Result<()> {
     // KIOSK MODE: Fullscreen borderless window (press ESC to exit for debugging)
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([1920.0, 1080.0])    // Force full resolution
-            .with_fullscreen(true)                // Start in fullscreen
+            .with_inner_size([config.display.width as f32, config.display.height as f32])  // Force full resolution
+            .with_fullscreen(config.display.fullscreen)  // Start in fullscreen
             .with_maximized(true)                 // Maximize if fullscreen fails
             .with_decorations(false)              // No title bar or borders
             .with_resizable(false)                // Cannot be resized
@@ -73,11 +85,14 @@ async fn main() -> Result<()> {
             Box::new(PixelSorterApp::new(
                 pixel_sorter,
                 camera_controller,
+                config,
+                config_error,
             ))
         }),
     )
     .map_err(|e| anyhow::anyhow!("Failed to run application: {}", e))?;
 
+    hardware::flush_battery_history();
     info!("Application shut down gracefully");
     Ok(())
 }
@@ -155,49 +170,3 @@ fn load_icon() -> egui::IconData {
         }
     }
 }
-
-fn load_ups_config() -> UpsConfig {
-    use std::fs;
-    
-    let config_path = "ups_config.toml";
-    
-    // Try to load from file
-    if let Ok(contents) = fs::read_to_string(config_path) {
-        // Simple TOML parsing for our needs
-        let mut config = UpsConfig::default();
-        
-        for line in contents.lines() {
-            let line = line.trim();
-            if line.starts_with('#') || line.is_empty() || line.starts_with('[') {
-                continue;
-            }
-            
-            if let Some((key, value)) = line.split_once('=') {
-                let key = key.trim();
-                let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
-                
-                match key {
-                    "enabled" => config.enabled = value == "true",
-                    "i2c_bus" => config.i2c_bus = value.parse().unwrap_or(1),
-                    "i2c_address" => {
-                        // Handle hex format: 0x36
-                        if value.starts_with("0x") || value.starts_with("0X") {
-                            config.i2c_address = u8::from_str_radix(&value[2..], 16).unwrap_or(0x36);
-                        } else {
-                            config.i2c_address = value.parse().unwrap_or(0x36);
-                        }
-                    }
-                    "voltage_threshold" => config.voltage_threshold = value.parse().unwrap_or(6.4),
-                    "check_interval_secs" => config.check_interval_secs = value.parse().unwrap_or(10),
-                    _ => {}
-                }
-            }
-        }
-        
-        info!("UPS configuration loaded from {}", config_path);
-        config
-    } else {
-        info!("No UPS config found at {}, using defaults (disabled)", config_path);
-        UpsConfig::default()
-    }
-}
\ No newline at end of file
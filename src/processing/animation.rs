@@ -0,0 +1,41 @@
+use anyhow::{anyhow, Result};
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+/// Assemble saved frames into a looping animated GIF.
+/// Frames are scaled down to `max_width` to keep the file shareable.
+pub fn encode_gif(frame_paths: &[PathBuf], output: &Path, frame_delay_ms: u32, max_width: u32) -> Result<()> {
+    if frame_paths.is_empty() {
+        return Err(anyhow!("No frames to assemble"));
+    }
+
+    let file = BufWriter::new(File::create(output)?);
+    let mut encoder = GifEncoder::new_with_speed(file, 10);
+    encoder.set_repeat(Repeat::Infinite)?;
+
+    let delay = Delay::from_numer_denom_ms(frame_delay_ms, 1);
+
+    for path in frame_paths {
+        let img = match image::open(path) {
+            Ok(img) => img,
+            Err(e) => {
+                log::warn!("Skipping unreadable frame {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        let img = if img.width() > max_width {
+            let height = (img.height() as u64 * max_width as u64 / img.width() as u64).max(1) as u32;
+            img.resize_exact(max_width, height, image::imageops::FilterType::Triangle)
+        } else {
+            img
+        };
+
+        encoder.encode_frame(Frame::from_parts(img.to_rgba8(), 0, 0, delay))?;
+    }
+
+    Ok(())
+}
@@ -29,7 +29,7 @@ impl PixelSorterApp {
         }
     }
 
-    fn apply_tint_to_image(&self, image: &mut image::RgbImage, tint_hue: f32) {
+    pub fn apply_tint_to_image(&self, image: &mut image::RgbImage, tint_hue: f32) {
         let (width, height) = image.dimensions();
         let tint_color = crate::processing::pixel_sorter::hue_to_rgb_pixel(tint_hue);
         let strength = 0.2; // Strength for tinting
@@ -77,26 +77,5 @@ impl PixelSorterApp {
     }
 
 
-    pub fn load_image(&mut self, ctx: &egui::Context) {
-        if let Some(path) = rfd::FileDialog::new()
-            .add_filter("Image Files", &["png", "jpg", "jpeg", "bmp", "tiff"])
-            .pick_file()
-        {
-            match image::open(&path) {
-                Ok(img) => {
-                    let rgb_image = img.to_rgb8();
-                    self.original_image = Some(rgb_image.clone());
-                    self.processed_image = Some(rgb_image.clone());
-                    self.create_processed_texture(ctx, rgb_image);
-                    self.preview_mode = false;
-                    self.current_phase = crate::ui::Phase::Edit; // Switch to edit phase
-                }
-                Err(_e) => {
-                    // Failed to load, silently ignore
-                }
-            }
-        }
-    }
-
         // Removed unused method save_image
 }
\ No newline at end of file
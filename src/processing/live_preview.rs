@@ -0,0 +1,51 @@
+use crate::PixelSorterApp;
+use image::RgbImage;
+use std::time::{Duration, Instant};
+
+const LIVE_SORT_MAX_WIDTH: u32 = 320;        // Preview frames are downscaled to this width before sorting
+const LIVE_SORT_FRAME_BUDGET_MS: f32 = 33.0; // Time one 30 FPS frame may spend on sorting
+const LIVE_SORT_COST_SMOOTHING: f32 = 0.2;   // Weight of the newest measurement in the cost average
+
+impl PixelSorterApp {
+    /// Run a fast, reduced-resolution sort on a preview frame.
+    /// Returns None when this frame should be skipped to keep the UI responsive.
+    pub fn sort_preview_frame(&mut self, frame: &RgbImage) -> Option<RgbImage> {
+        if self.live_sort_frames_to_skip > 0 {
+            self.live_sort_frames_to_skip -= 1;
+            return None;
+        }
+
+        let start = Instant::now();
+
+        let small = if frame.width() > LIVE_SORT_MAX_WIDTH {
+            let height = (frame.height() * LIVE_SORT_MAX_WIDTH / frame.width()).max(1);
+            image::imageops::resize(frame, LIVE_SORT_MAX_WIDTH, height, image::imageops::FilterType::Nearest)
+        } else {
+            frame.clone()
+        };
+
+        let mut sorted = self
+            .pixel_sorter
+            .sort_pixels(&small, self.current_algorithm, &self.sorting_params)
+            .ok()?;
+
+        if self.tint_enabled && self.sorting_params.color_tint > 0.0 {
+            self.apply_tint_to_image(&mut sorted, self.sorting_params.color_tint);
+        }
+
+        // Adaptive frame skipping: skip as many frames as the sort took frame budgets
+        self.record_live_sort_cost(start.elapsed());
+        self.live_sort_frames_to_skip = (self.live_sort_cost_ms / LIVE_SORT_FRAME_BUDGET_MS) as u32;
+
+        Some(sorted)
+    }
+
+    fn record_live_sort_cost(&mut self, cost: Duration) {
+        let cost_ms = cost.as_secs_f32() * 1000.0;
+        self.live_sort_cost_ms = if self.live_sort_cost_ms == 0.0 {
+            cost_ms
+        } else {
+            self.live_sort_cost_ms * (1.0 - LIVE_SORT_COST_SMOOTHING) + cost_ms * LIVE_SORT_COST_SMOOTHING
+        };
+    }
+}
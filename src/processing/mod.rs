@@ -4,6 +4,10 @@ pub mod pixel_sorter;
 pub mod image_ops;
 pub mod crop;
 pub mod texture;
+pub mod animation;
+pub mod live_preview;
+pub mod slit_scan;
+pub mod output_format;
 
 // Re-export commonly used types
 pub use pixel_sorter::{PixelSorter, SortingAlgorithm, SortingParameters};
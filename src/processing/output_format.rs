@@ -0,0 +1,127 @@
+use anyhow::Result;
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::codecs::tiff::TiffEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{ImageEncoder, RgbImage};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Added to the master's file stem for the small shareable copy
+pub const SHARE_SUFFIX: &str = "_share";
+
+/// File format for saved iterations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Png(CompressionType),
+    Jpeg { quality: u8 },
+    /// Lossless; the bundled encoder has no lossy mode
+    WebP,
+    /// Uncompressed
+    Tiff,
+}
+
+impl OutputFormat {
+    /// Format from the `[output]` config keys; None for unknown names
+    pub fn from_config(format: &str, png_compression: &str, jpeg_quality: u8) -> Option<OutputFormat> {
+        match format {
+            "png" => png_compression_type(png_compression).map(OutputFormat::Png),
+            "jpeg" | "jpg" => Some(OutputFormat::Jpeg { quality: jpeg_quality.clamp(1, 100) }),
+            "webp" => Some(OutputFormat::WebP),
+            "tiff" | "tif" => Some(OutputFormat::Tiff),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Png(_) => "png",
+            OutputFormat::Jpeg { .. } => "jpg",
+            OutputFormat::WebP => "webp",
+            OutputFormat::Tiff => "tiff",
+        }
+    }
+}
+
+/// "fast", "default" or "best"
+pub fn png_compression_type(name: &str) -> Option<CompressionType> {
+    match name {
+        "fast" => Some(CompressionType::Fast),
+        "default" => Some(CompressionType::Default),
+        "best" => Some(CompressionType::Best),
+        _ => None,
+    }
+}
+
+/// Encode `image` to `path` in the given format
+pub fn save_image(image: &RgbImage, path: &Path, format: OutputFormat) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    let (width, height) = image.dimensions();
+    let color = image::ColorType::Rgb8;
+    match format {
+        OutputFormat::Png(compression) => {
+            PngEncoder::new_with_quality(&mut file, compression, FilterType::Adaptive).write_image(image, width, height, color)?
+        }
+        OutputFormat::Jpeg { quality } => JpegEncoder::new_with_quality(&mut file, quality).write_image(image, width, height, color)?,
+        OutputFormat::WebP => WebPEncoder::new_lossless(&mut file).write_image(image, width, height, color)?,
+        OutputFormat::Tiff => TiffEncoder::new(&mut file).write_image(image, width, height, color)?,
+    }
+    // Dropping the writer would swallow a failed final write, e.g. on a full disk
+    file.flush()?;
+    Ok(())
+}
+
+/// Small JPEG for sharing, scaled so its longer side is at most `max_size`
+pub fn save_share_copy(image: &RgbImage, path: &Path, max_size: u32, quality: u8) -> Result<()> {
+    let (width, height) = image.dimensions();
+    if width.max(height) <= max_size {
+        return save_image(image, path, OutputFormat::Jpeg { quality });
+    }
+    let scale = max_size as f32 / width.max(height) as f32;
+    let small = image::imageops::thumbnail(
+        image,
+        ((width as f32 * scale).round() as u32).max(1),
+        ((height as f32 * scale).round() as u32).max(1),
+    );
+    save_image(&small, path, OutputFormat::Jpeg { quality })
+}
+
+/// Path of the share copy that goes with a saved master
+pub fn share_copy_path(master: &Path) -> std::path::PathBuf {
+    let stem = master.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    master.with_file_name(format!("{}{}.jpg", stem, SHARE_SUFFIX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let image = RgbImage::from_fn(64, 32, |x, y| image::Rgb([(x * 4) as u8, (y * 8) as u8, 128]));
+
+        for format in ["png", "jpeg", "webp", "tiff"] {
+            let format = OutputFormat::from_config(format, "best", 85).unwrap();
+            let path = dir.join(format!("edit_001.{}", format.extension()));
+            save_image(&image, &path, format).unwrap();
+            let loaded = image::open(&path).unwrap().to_rgb8();
+            assert_eq!(loaded.dimensions(), (64, 32), "{:?}", format);
+            if format != (OutputFormat::Jpeg { quality: 85 }) {
+                assert_eq!(loaded, image, "{:?} should be lossless", format);
+            }
+        }
+
+        let master = dir.join("edit_001_horizontal.png");
+        let share = share_copy_path(&master);
+        assert_eq!(share, dir.join("edit_001_horizontal_share.jpg"));
+        save_share_copy(&image, &share, 16, 80).unwrap();
+        assert_eq!(image::open(&share).unwrap().to_rgb8().dimensions(), (16, 8));
+
+        assert!(OutputFormat::from_config("gif", "best", 85).is_none());
+        assert!(OutputFormat::from_config("png", "maximum", 85).is_none());
+
+    }
+}
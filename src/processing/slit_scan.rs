@@ -0,0 +1,144 @@
+use image::{imageops, RgbImage};
+
+/// Which slice of each frame is sampled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SlitOrientation {
+    /// Centre column of each frame, frames laid out left to right
+    Column,
+    /// Centre row of each frame, frames laid out top to bottom
+    Row,
+}
+
+impl SlitOrientation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SlitOrientation::Column => "Cols",
+            SlitOrientation::Row => "Rows",
+        }
+    }
+
+    pub fn next(&self) -> SlitOrientation {
+        match self {
+            SlitOrientation::Column => SlitOrientation::Row,
+            SlitOrientation::Row => SlitOrientation::Column,
+        }
+    }
+}
+
+/// Builds a slit-scan image from one pixel column or row of each successive frame
+pub struct SlitScan {
+    orientation: SlitOrientation,
+    /// Output image, allocated from the first frame's dimensions
+    image: Option<RgbImage>,
+    /// Number of slices written so far
+    filled: u32,
+}
+
+impl SlitScan {
+    pub fn new(orientation: SlitOrientation) -> Self {
+        Self {
+            orientation,
+            image: None,
+            filled: 0,
+        }
+    }
+
+    /// Number of slices needed to complete the image (matches the frame size)
+    pub fn target_slices(&self) -> u32 {
+        match (&self.image, self.orientation) {
+            (Some(img), SlitOrientation::Column) => img.width(),
+            (Some(img), SlitOrientation::Row) => img.height(),
+            (None, _) => 0,
+        }
+    }
+
+    pub fn slices(&self) -> u32 {
+        self.filled
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.image.is_some() && self.filled >= self.target_slices()
+    }
+
+    /// Sample the centre slice of a frame. Frames whose size doesn't match the first one are ignored.
+    pub fn push_frame(&mut self, frame: &RgbImage) {
+        if self.is_complete() || frame.width() == 0 || frame.height() == 0 {
+            return;
+        }
+
+        let image = self
+            .image
+            .get_or_insert_with(|| RgbImage::new(frame.width(), frame.height()));
+        if image.dimensions() != frame.dimensions() {
+            return;
+        }
+
+        match self.orientation {
+            SlitOrientation::Column => {
+                let src_x = frame.width() / 2;
+                for y in 0..frame.height() {
+                    image.put_pixel(self.filled, y, *frame.get_pixel(src_x, y));
+                }
+            }
+            SlitOrientation::Row => {
+                let src_y = frame.height() / 2;
+                for x in 0..frame.width() {
+                    image.put_pixel(x, self.filled, *frame.get_pixel(x, src_y));
+                }
+            }
+        }
+        self.filled += 1;
+    }
+
+    /// The image built so far, trimmed to the slices actually captured
+    pub fn to_image(&self) -> Option<RgbImage> {
+        let image = self.image.as_ref()?;
+        if self.filled == 0 {
+            return None;
+        }
+
+        let (width, height) = match self.orientation {
+            SlitOrientation::Column => (self.filled, image.height()),
+            SlitOrientation::Row => (image.width(), self.filled),
+        };
+        Some(imageops::crop_imm(image, 0, 0, width, height).to_image())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgb;
+
+    fn solid_frame(value: u8) -> RgbImage {
+        RgbImage::from_pixel(4, 3, Rgb([value, value, value]))
+    }
+
+    #[test]
+    fn test_column_scan_lays_frames_left_to_right() {
+        let mut scan = SlitScan::new(SlitOrientation::Column);
+        scan.push_frame(&solid_frame(10));
+        scan.push_frame(&solid_frame(20));
+
+        let partial = scan.to_image().unwrap();
+        assert_eq!(partial.dimensions(), (2, 3));
+        assert_eq!(partial.get_pixel(0, 0), &Rgb([10, 10, 10]));
+        assert_eq!(partial.get_pixel(1, 2), &Rgb([20, 20, 20]));
+
+        scan.push_frame(&solid_frame(30));
+        scan.push_frame(&solid_frame(40));
+        assert!(scan.is_complete());
+        assert_eq!(scan.to_image().unwrap().dimensions(), (4, 3));
+    }
+
+    #[test]
+    fn test_row_scan_ignores_mismatched_frames() {
+        let mut scan = SlitScan::new(SlitOrientation::Row);
+        scan.push_frame(&solid_frame(10));
+        scan.push_frame(&RgbImage::new(8, 8));
+
+        assert_eq!(scan.slices(), 1);
+        assert_eq!(scan.target_slices(), 3);
+        assert_eq!(scan.to_image().unwrap().dimensions(), (4, 1));
+    }
+}
@@ -0,0 +1,208 @@
+// Finding images to open or import, on a removable drive or in local folders
+// Drives are searched a few folders deep; local folders are browsed one level at a time.
+// Chosen files are copied into a new session as import_NNN_<name>
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Extensions the image crate can open, matched case-insensitively
+pub const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "bmp", "tif", "tiff", "webp"];
+/// How many folders below the drive root are searched
+const MAX_DEPTH: usize = 4;
+/// Stop listing after this many images so a huge photo library stays responsive
+const MAX_IMAGES: usize = 500;
+/// Folders operating systems leave on drives
+const SKIPPED_DIRS: &[&str] = &["System Volume Information", "$RECYCLE.BIN", "lost+found"];
+
+/// An image found on a drive or in a folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageFile {
+    pub path: PathBuf,
+    /// Name shown in the browser (relative to the drive root when searching a drive)
+    pub display_name: String,
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// Order of images in the browser
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    NewestFirst,
+    OldestFirst,
+    NameAscending,
+    NameDescending,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 4] = [SortOrder::NewestFirst, SortOrder::OldestFirst, SortOrder::NameAscending, SortOrder::NameDescending];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::NewestFirst => "Newest",
+            SortOrder::OldestFirst => "Oldest",
+            SortOrder::NameAscending => "Name A-Z",
+            SortOrder::NameDescending => "Name Z-A",
+        }
+    }
+
+    pub fn sort(&self, images: &mut [ImageFile]) {
+        let by_name = |a: &ImageFile, b: &ImageFile| a.display_name.to_lowercase().cmp(&b.display_name.to_lowercase());
+        match self {
+            SortOrder::NewestFirst => images.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| by_name(a, b))),
+            SortOrder::OldestFirst => images.sort_by(|a, b| a.modified.cmp(&b.modified).then_with(|| by_name(a, b))),
+            SortOrder::NameAscending => images.sort_by(by_name),
+            SortOrder::NameDescending => images.sort_by(|a, b| by_name(b, a)),
+        }
+    }
+}
+
+/// Contents of one folder: subfolders (by name) and images (in no particular order)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FolderListing {
+    pub folders: Vec<PathBuf>,
+    pub images: Vec<ImageFile>,
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| IMAGE_EXTENSIONS.iter().any(|known| known.eq_ignore_ascii_case(ext)))
+}
+
+/// Images anywhere on the drive, newest first. Hidden files and folders are skipped.
+/// Stops walking once MAX_IMAGES are found. Slow on big drives, so call it off the UI thread.
+pub fn find_images(root: &Path) -> Vec<ImageFile> {
+    let mut images = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        let listing = list_folder(&dir);
+        if depth < MAX_DEPTH {
+            pending.extend(listing.folders.into_iter().map(|folder| (folder, depth + 1)));
+        }
+        for mut image in listing.images.into_iter().take(MAX_IMAGES - images.len()) {
+            image.display_name = image.path.strip_prefix(root).unwrap_or(&image.path).display().to_string();
+            images.push(image);
+        }
+        if images.len() >= MAX_IMAGES {
+            log::info!("Stopped searching {} after {} images", root.display(), MAX_IMAGES);
+            break;
+        }
+    }
+
+    SortOrder::NewestFirst.sort(&mut images);
+    images
+}
+
+/// Subfolders and images directly inside `dir`, skipping hidden and system entries
+pub fn list_folder(dir: &Path) -> FolderListing {
+    let mut listing = FolderListing::default();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return listing;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_str()) {
+            continue;
+        }
+        let path = entry.path();
+        // Follows symlinks, so linked folders can be browsed
+        let Ok(metadata) = std::fs::metadata(&path) else {
+            continue;
+        };
+
+        if metadata.is_dir() {
+            listing.folders.push(path);
+        } else if is_image(&path) {
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs());
+            listing.images.push(ImageFile { path, display_name: name, size: metadata.len(), modified });
+        }
+    }
+
+    listing.folders.sort_by_key(|folder| folder.file_name().map(|name| name.to_string_lossy().to_lowercase()));
+    listing
+}
+
+/// Copy the files into the session folder as import_001_<name>, import_002_<name>, ...
+/// Returns the new paths in the same order.
+pub fn import_images(files: &[PathBuf], session_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(session_dir)?;
+
+    let mut imported = Vec::with_capacity(files.len());
+    for (index, source) in files.iter().enumerate() {
+        let name = source
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "image".to_string());
+        let destination = session_dir.join(format!("import_{:03}_{}", index + 1, name));
+        std::fs::copy(source, &destination)
+            .map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", source.display(), e)))?;
+        imported.push(destination);
+    }
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_and_import() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let drive = root.join("drive");
+        std::fs::create_dir_all(drive.join("DCIM/100CANON")).unwrap();
+        std::fs::create_dir_all(drive.join(".Trashes")).unwrap();
+        std::fs::write(drive.join("DCIM/100CANON/IMG_0001.JPG"), "jpeg").unwrap();
+        std::fs::write(drive.join("photo.png"), "png").unwrap();
+        std::fs::write(drive.join("notes.txt"), "text").unwrap();
+        std::fs::write(drive.join(".Trashes/deleted.jpg"), "gone").unwrap();
+
+        let mut images = find_images(&drive);
+        images.sort_by(|a, b| a.display_name.cmp(&b.display_name));
+        let names: Vec<&str> = images.iter().map(|image| image.display_name.as_str()).collect();
+        assert_eq!(names, vec!["DCIM/100CANON/IMG_0001.JPG", "photo.png"]);
+
+        let session = root.join("session_1");
+        let files: Vec<PathBuf> = images.iter().map(|image| image.path.clone()).collect();
+        let imported = import_images(&files, &session).unwrap();
+        assert_eq!(imported, vec![session.join("import_001_IMG_0001.JPG"), session.join("import_002_photo.png")]);
+        assert_eq!(std::fs::read_to_string(&imported[1]).unwrap(), "png");
+
+    }
+
+    #[test]
+    fn test_list_folder_and_sort() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join("Holiday")).unwrap();
+        std::fs::create_dir_all(dir.join("archive")).unwrap();
+        std::fs::write(dir.join("b.jpg"), "b").unwrap();
+        std::fs::write(dir.join("A.png"), "a").unwrap();
+        std::fs::write(dir.join("readme.md"), "text").unwrap();
+        std::fs::write(dir.join("Holiday/beach.jpg"), "nested").unwrap();
+
+        let mut listing = list_folder(dir);
+        assert_eq!(listing.folders, vec![dir.join("archive"), dir.join("Holiday")]);
+        assert_eq!(listing.images.len(), 2);
+
+        let names = |images: &[ImageFile]| images.iter().map(|image| image.display_name.clone()).collect::<Vec<_>>();
+        SortOrder::NameAscending.sort(&mut listing.images);
+        assert_eq!(names(&listing.images), vec!["A.png", "b.jpg"]);
+        SortOrder::NameDescending.sort(&mut listing.images);
+        assert_eq!(names(&listing.images), vec!["b.jpg", "A.png"]);
+
+        listing.images[0].modified = 10;
+        listing.images[1].modified = 20;
+        SortOrder::NewestFirst.sort(&mut listing.images);
+        assert_eq!(names(&listing.images), vec!["A.png", "b.jpg"]);
+        SortOrder::OldestFirst.sort(&mut listing.images);
+        assert_eq!(names(&listing.images), vec!["b.jpg", "A.png"]);
+
+    }
+}
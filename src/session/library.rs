@@ -0,0 +1,329 @@
+// Saved sessions on disk, for choosing what to export
+// Favourite iterations are listed in a small text file inside each session folder,
+// and a marker file records that the whole session has been exported
+
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::processing::output_format;
+
+/// One file name per line, inside the session folder
+pub const FAVOURITES_FILE: &str = "favourites.txt";
+/// Written into a session folder once every file in it has been exported and verified
+pub const EXPORTED_MARKER: &str = ".exported";
+
+/// Which files of the selected sessions an export includes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportMode {
+    Everything,
+    /// The last iteration of each session
+    FinalsOnly,
+    FavouritesOnly,
+}
+
+impl ExportMode {
+    pub const ALL: [ExportMode; 3] = [ExportMode::Everything, ExportMode::FinalsOnly, ExportMode::FavouritesOnly];
+
+    /// Name used in pixelsort.toml
+    pub fn from_name(name: &str) -> Option<ExportMode> {
+        match name {
+            "everything" => Some(ExportMode::Everything),
+            "finals" => Some(ExportMode::FinalsOnly),
+            "favourites" | "favorites" => Some(ExportMode::FavouritesOnly),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportMode::Everything => "everything",
+            ExportMode::FinalsOnly => "finals",
+            ExportMode::FavouritesOnly => "favourites",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportMode::Everything => "Everything",
+            ExportMode::FinalsOnly => "Finals only",
+            ExportMode::FavouritesOnly => "★ Favourites only",
+        }
+    }
+}
+
+/// A saved edit (edit_NNN_...) in a session folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct Iteration {
+    pub file_name: String,
+    pub number: u32,
+    pub favourite: bool,
+    /// Small JPEG saved alongside, exported together with the iteration
+    pub share_file: Option<String>,
+}
+
+impl Iteration {
+    fn file_names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.file_name.as_str()).chain(self.share_file.as_deref())
+    }
+}
+
+/// A session_* folder in the output directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct Session {
+    pub name: String,
+    /// Edits, oldest first
+    pub iterations: Vec<Iteration>,
+    /// Every other file (original capture, imports, ...)
+    pub other_files: Vec<String>,
+}
+
+impl Session {
+    pub fn final_iteration(&self) -> Option<&Iteration> {
+        self.iterations.iter().max_by_key(|iteration| iteration.number)
+    }
+
+    /// Files exported in `mode`, relative to the output folder
+    pub fn files_for_mode(&self, mode: ExportMode) -> Vec<PathBuf> {
+        let names: Vec<&str> = match mode {
+            ExportMode::Everything => self
+                .other_files
+                .iter()
+                .map(String::as_str)
+                .chain(self.iterations.iter().flat_map(Iteration::file_names))
+                .collect(),
+            ExportMode::FinalsOnly => self.final_iteration().into_iter().flat_map(Iteration::file_names).collect(),
+            ExportMode::FavouritesOnly => self
+                .iterations
+                .iter()
+                .filter(|iteration| iteration.favourite)
+                .flat_map(Iteration::file_names)
+                .collect(),
+        };
+        names.into_iter().map(|name| Path::new(&self.name).join(name)).collect()
+    }
+}
+
+/// Sessions in the output folder, newest first
+pub fn scan_sessions(output_dir: &Path) -> Vec<Session> {
+    let Ok(entries) = std::fs::read_dir(output_dir) else {
+        return Vec::new();
+    };
+
+    let mut sessions: Vec<Session> = entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.starts_with("session_").then(|| load_session(&entry.path(), name))
+        })
+        .collect();
+    // Folder names are session_YYYYMMDD_HHMMSS, so they sort by date
+    sessions.sort_by(|a, b| b.name.cmp(&a.name));
+    sessions
+}
+
+fn load_session(dir: &Path, name: String) -> Session {
+    let favourites = read_favourites(dir);
+
+    let mut files: Vec<String> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|entry| entry.path().is_file())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .filter(|file_name| file_name != FAVOURITES_FILE && !file_name.starts_with('.'))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+
+    let (share_files, files): (Vec<String>, Vec<String>) = files.into_iter().partition(|file_name| is_share_copy(file_name));
+
+    let mut iterations = Vec::new();
+    let mut other_files = Vec::new();
+    for file_name in files {
+        match iteration_number(&file_name) {
+            Some(number) => {
+                let share_name = share_copy_name(&file_name);
+                iterations.push(Iteration {
+                    favourite: favourites.contains(&file_name),
+                    share_file: share_files.iter().find(|share| **share == share_name).cloned(),
+                    file_name,
+                    number,
+                })
+            }
+            None => other_files.push(file_name),
+        }
+    }
+    iterations.sort_by_key(|iteration| iteration.number);
+
+    // A share copy whose master is gone is just another file
+    for share in share_files {
+        if !iterations.iter().any(|iteration| iteration.share_file.as_ref() == Some(&share)) {
+            other_files.push(share);
+        }
+    }
+    other_files.sort();
+
+    Session { name, iterations, other_files }
+}
+
+/// "edit_007_horizontal_....png" → 7
+fn iteration_number(file_name: &str) -> Option<u32> {
+    let rest = file_name.strip_prefix("edit_")?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// "edit_007_horizontal.png" → "edit_007_horizontal_share.jpg"
+fn share_copy_name(file_name: &str) -> String {
+    let stem = file_name.rsplit_once('.').map_or(file_name, |(stem, _)| stem);
+    format!("{}{}.jpg", stem, output_format::SHARE_SUFFIX)
+}
+
+fn is_share_copy(file_name: &str) -> bool {
+    file_name.starts_with("edit_") && file_name.ends_with(&format!("{}.jpg", output_format::SHARE_SUFFIX))
+}
+
+fn read_favourites(session_dir: &Path) -> BTreeSet<String> {
+    std::fs::read_to_string(session_dir.join(FAVOURITES_FILE))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Star or unstar an iteration; the favourites file is removed when it becomes empty
+pub fn set_favourite(output_dir: &Path, session: &str, file_name: &str, favourite: bool) -> std::io::Result<()> {
+    let session_dir = output_dir.join(session);
+    let mut favourites = read_favourites(&session_dir);
+    if favourite {
+        favourites.insert(file_name.to_string());
+    } else {
+        favourites.remove(file_name);
+    }
+
+    let path = session_dir.join(FAVOURITES_FILE);
+    if favourites.is_empty() {
+        return match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    }
+    let contents: String = favourites.iter().map(|name| format!("{}\n", name)).collect();
+    std::fs::write(path, contents)
+}
+
+/// Record which files of the session are on a drive. The marker's time is set to `listed_at`,
+/// when the export listed the files, so anything saved or changed after that doesn't count.
+pub fn mark_exported(session_dir: &Path, files: &[String], listed_at: SystemTime) -> std::io::Result<()> {
+    let path = session_dir.join(EXPORTED_MARKER);
+    let contents: String = files.iter().map(|name| format!("{}\n", name)).collect();
+    std::fs::write(&path, contents)?;
+    std::fs::File::options().write(true).open(&path)?.set_modified(listed_at)
+}
+
+/// True if every file in the session was in its last export and none changed since
+pub fn is_exported(session_dir: &Path) -> bool {
+    let modified = |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let marker = session_dir.join(EXPORTED_MARKER);
+    let Some(exported_at) = modified(&marker) else {
+        return false;
+    };
+    let exported: BTreeSet<String> = std::fs::read_to_string(&marker).unwrap_or_default().lines().map(str::to_string).collect();
+
+    std::fs::read_dir(session_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                // Starring an iteration afterwards doesn't change what was exported
+                .filter(|entry| entry.file_name() != EXPORTED_MARKER && entry.file_name() != FAVOURITES_FILE)
+                .all(|entry| {
+                    exported.contains(entry.file_name().to_string_lossy().as_ref())
+                        && modified(&entry.path()).is_some_and(|time| time <= exported_at)
+                })
+        })
+        .unwrap_or(false)
+}
+
+/// Files exported in `mode` across `sessions`, relative to the output folder
+pub fn files_for_mode<'a>(sessions: impl IntoIterator<Item = &'a Session>, mode: ExportMode) -> BTreeSet<PathBuf> {
+    sessions.into_iter().flat_map(|session| session.files_for_mode(mode)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sessions_and_modes() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path();
+        let session = output.join("session_20240101_120000");
+        std::fs::create_dir_all(&session).unwrap();
+        std::fs::create_dir_all(output.join("session_20240102_090000")).unwrap();
+        std::fs::create_dir_all(output.join("not_a_session")).unwrap();
+        let names = [
+            "original.png",
+            "edit_001_horizontal.png",
+            "edit_002_vertical.png",
+            "edit_010_diagonal.jpg",
+            "edit_010_diagonal_share.jpg",
+        ];
+        for name in names {
+            std::fs::write(session.join(name), b"x").unwrap();
+        }
+
+        set_favourite(output, "session_20240101_120000", "edit_002_vertical.png", true).unwrap();
+        let sessions = scan_sessions(output);
+        assert_eq!(sessions.len(), 2);
+        assert_eq!(sessions[0].name, "session_20240102_090000");
+        let edits = &sessions[1];
+        assert_eq!(edits.iterations.len(), 3);
+        assert_eq!(edits.other_files, vec!["original.png".to_string()]);
+        assert_eq!(edits.final_iteration().unwrap().number, 10);
+        assert_eq!(edits.final_iteration().unwrap().share_file.as_deref(), Some("edit_010_diagonal_share.jpg"));
+
+        let relative = |name: &str| Path::new("session_20240101_120000").join(name);
+        assert_eq!(files_for_mode(&sessions, ExportMode::Everything).len(), 5);
+        assert_eq!(
+            files_for_mode(&sessions, ExportMode::FinalsOnly),
+            BTreeSet::from([relative("edit_010_diagonal.jpg"), relative("edit_010_diagonal_share.jpg")])
+        );
+        assert_eq!(files_for_mode(&sessions, ExportMode::FavouritesOnly), BTreeSet::from([relative("edit_002_vertical.png")]));
+
+        set_favourite(output, "session_20240101_120000", "edit_002_vertical.png", false).unwrap();
+        assert!(!session.join(FAVOURITES_FILE).exists());
+        assert!(files_for_mode(&scan_sessions(output), ExportMode::FavouritesOnly).is_empty());
+
+        assert!(!is_exported(&session));
+        let exported: Vec<String> = names.iter().map(|name| name.to_string()).collect();
+        mark_exported(&session, &exported, SystemTime::now()).unwrap();
+        assert!(is_exported(&session));
+        assert_eq!(scan_sessions(output)[1].other_files, vec!["original.png".to_string()]);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(session.join("edit_011_new.png"), b"x").unwrap();
+        assert!(!is_exported(&session));
+
+        // Saved while the export ran: older than the marker, but not in the export
+        std::fs::remove_file(session.join("edit_011_new.png")).unwrap();
+        std::fs::write(session.join("edit_012_during.png"), b"x").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        mark_exported(&session, &exported, SystemTime::now()).unwrap();
+        assert!(!is_exported(&session));
+
+    }
+
+    #[test]
+    fn test_mode_names() {
+        assert_eq!(ExportMode::from_name("finals"), Some(ExportMode::FinalsOnly));
+        assert_eq!(ExportMode::from_name("favourites"), Some(ExportMode::FavouritesOnly));
+        assert_eq!(ExportMode::from_name("all"), None);
+        for mode in ExportMode::ALL {
+            assert_eq!(ExportMode::from_name(mode.name()), Some(mode));
+        }
+    }
+}
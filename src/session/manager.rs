@@ -1,11 +1,19 @@
 use crate::PixelSorterApp;
 use eframe::egui;
 use std::path::PathBuf;
+use std::time::Instant;
 use chrono::{DateTime, Local};
 use crate::processing::SortingAlgorithm;
+use crate::hardware::RemovableDrive;
+use super::usb_export::{ExportStatus, ExportTarget, UsbExportJob};
+use super::writer::{ShareCopy, WriteRequest};
+
+/// How long a low-battery shutdown waits for queued iteration writes
+const SHUTDOWN_WRITE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
 
 impl PixelSorterApp {
-    fn auto_save_image(&mut self, image: &image::RgbImage, algorithm: &SortingAlgorithm) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    /// Create the session folder on first use and return its path
+    pub fn ensure_session_dir(&mut self) -> Result<PathBuf, Box<dyn std::error::Error>> {
         // Create session folder if this is the first save
         if self.current_session_folder.is_none() {
             let now: DateTime<Local> = Local::now();
@@ -15,120 +23,223 @@ impl PixelSorterApp {
         }
         
         // Create session directory
-        let session_dir = PathBuf::from("sorted_images").join(self.current_session_folder.as_ref().unwrap());
+        let session_dir = self.config.paths.output_dir.join(self.current_session_folder.as_ref().unwrap());
         std::fs::create_dir_all(&session_dir)?;
+        Ok(session_dir)
+    }
+
+    /// Write request for the next iteration number in the configured format
+    fn next_iteration_request(&mut self, image: image::RgbImage, algorithm: &SortingAlgorithm) -> Result<WriteRequest, Box<dyn std::error::Error>> {
+        let session_dir = self.ensure_session_dir()?;
         
-        // Increment iteration counter for this save
-        self.iteration_counter += 1;
-        
-        // Generate iteration-based filename
-        let filename = format!("edit_{:03}_{}.png", 
-            self.iteration_counter,
-            algorithm.name().to_lowercase()
+        // Generate iteration-based filename in the configured format
+        let format = self.config.output_format();
+        let filename = format!("edit_{:03}_{}.{}", 
+            self.iteration_counter + 1,
+            algorithm.name().to_lowercase(),
+            format.extension()
         );
         
-        let save_path = session_dir.join(filename);
-        image.save(&save_path)?;
-        
-        // Return the path for potential loading in next iteration
+        let output = &self.config.output;
+        Ok(WriteRequest {
+            image,
+            path: session_dir.join(filename),
+            format,
+            share_copy: output.share_copy.then_some(ShareCopy {
+                max_size: output.share_max_size,
+                quality: output.share_quality,
+            }),
+        })
+    }
+
+    /// Number for the next burst or time-lapse in the current session, one more than the
+    /// highest `<prefix>NN` on disk or still queued for writing (burst_NN_MM.png, timelapse_NN.gif, ...)
+    pub fn next_sequence(&mut self, prefix: &str) -> Result<u32, Box<dyn std::error::Error>> {
+        let session_dir = self.ensure_session_dir()?;
+        let on_disk = std::fs::read_dir(&session_dir)?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string());
+        let queued = self
+            .image_writer
+            .pending_paths()
+            .iter()
+            .filter_map(|path| path.file_name().map(|name| name.to_string_lossy().to_string()));
+        let last = on_disk
+            .chain(queued)
+            .filter_map(|name| {
+                let sequence = name.strip_prefix(prefix)?.split(['_', '.']).next()?;
+                sequence.parse::<u32>().ok()
+            })
+            .max()
+            .unwrap_or(0);
+        Ok(last + 1)
+    }
+
+    /// Queue a raw burst frame for saving in the configured format (burst_01_01.png, burst_01_02.png, ...).
+    /// Like iterations it's written in the background; failures are reported by poll_image_writes.
+    pub fn save_burst_frame(&mut self, image: &image::RgbImage, sequence: u32, index: usize) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let session_dir = self.ensure_session_dir()?;
+        let format = self.config.output_format();
+        let save_path = session_dir.join(format!("burst_{:02}_{:02}.{}", sequence, index + 1, format.extension()));
+        self.image_writer.queue(WriteRequest {
+            image: image.clone(),
+            path: save_path.clone(),
+            format,
+            share_copy: None,
+        });
         Ok(save_path)
     }
 
-    pub fn copy_to_usb(&self) -> Result<(), Box<dyn std::error::Error>> {
-        #[cfg(target_os = "linux")]
-        {
-            use std::process::Command;
-            
-            log::info!("Searching for USB drives via mount points...");
-            
-            // Check mounted filesystems for USB drives
-            let output = Command::new("mount").output()?;
-            let mount_output = String::from_utf8_lossy(&output.stdout);
-            
-            let mut usb_found = false;
-            
-            // Look for USB filesystem types mounted under /media/
-            for line in mount_output.lines() {
-                if line.contains("/media/") && 
-                   (line.contains("exfat") || line.contains("vfat") || line.contains("ntfs")) {
-                    // Extract the mount point (between "on " and " type")
-                    if let Some(on_idx) = line.find(" on ") {
-                        if let Some(type_idx) = line.find(" type ") {
-                            let mount_point = &line[on_idx + 4..type_idx];
-                            log::info!("Found USB drive at: {}", mount_point);
-                            
-                            let usb_path = std::path::PathBuf::from(mount_point);
-                            
-                            // Test if we can write to it
-                            let test_file = usb_path.join(".pixelsort_test");
-                            if std::fs::write(&test_file, "test").is_ok() {
-                                let _ = std::fs::remove_file(&test_file);
-                                
-                                log::info!("Writable USB confirmed at: {}", mount_point);
-                                
-                                // Try to copy sorted_images folder to USB
-                                let dest_path = usb_path.join("pixelsort_export");
-                                match Self::copy_directory(
-                                    PathBuf::from("sorted_images"),
-                                    dest_path.clone(),
-                                ) {
-                                    Ok(()) => {
-                                        log::info!("Successfully copied to USB: {}", dest_path.display());
-                                        usb_found = true;
-                                        break;
-                                    }
-                                    Err(e) => {
-                                        log::warn!("Failed to copy to {}: {}", dest_path.display(), e);
-                                        return Err(format!("Copy failed: {}", e).into());
-                                    }
-                                }
-                            } else {
-                                log::warn!("Cannot write to USB at: {}", mount_point);
-                            }
-                        }
-                    }
-                }
-            }
-            
-            if !usb_found {
-                log::warn!("No writable USB drive found in mount points");
-                return Err("No writable USB drive found".into());
-            }
-            
-            Ok(())
+    /// Save everything in progress before a low-battery shutdown: the current edit,
+    /// any partial slit-scan, and a session_state.toml describing where the user was.
+    /// Returns None without creating a session folder when there is nothing to save.
+    pub fn save_session_for_shutdown(&mut self) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+        // Burst and time-lapse frames are already on disk or queued; stop capturing more
+        self.countdown_start = None;
+        self.burst = None;
+        if self.timelapse.is_some() {
+            self.stop_timelapse();
         }
-        
-        #[cfg(not(target_os = "linux"))]
-        {
-            Err("USB export only available on Linux".into())
+        let partial_scan = self.slit_scan.take().and_then(|scan| scan.to_image());
+
+        if self.processed_image.is_none() && partial_scan.is_none() && self.image_writer.pending() == 0 {
+            log::info!("Nothing to save before shutdown");
+            return Ok(None);
+        }
+        let session_dir = self.ensure_session_dir()?;
+
+        // Iterations still being written must reach the disk before power goes
+        for failed in self.image_writer.wait_idle(SHUTDOWN_WRITE_TIMEOUT).into_iter().filter(|write| write.result.is_err()) {
+            log::error!("Iteration {} was not saved before shutdown", failed.path.display());
+        }
+
+        if let Some(partial) = partial_scan {
+            partial.save(session_dir.join("slit_scan_partial.png"))?;
+        }
+
+        // One file overwritten each time, so a cancelled countdown doesn't add iterations
+        if let Some(processed) = &self.processed_image {
+            let saved = session_dir.join("shutdown_autosave.png");
+            processed.save(&saved)?;
+            log::info!("Saved current edit to {}", saved.display());
         }
+
+        let now: DateTime<Local> = Local::now();
+        let state = format!(
+            "# Saved on low battery at {}\n\
+             phase = \"{:?}\"\n\
+             algorithm = \"{}\"\n\
+             sort_mode = \"{}\"\n\
+             threshold = {}\n\
+             hue_shift = {}\n\
+             tint_enabled = {}\n\
+             color_tint = {}\n\
+             iteration = {}\n",
+            now.format("%Y-%m-%d %H:%M:%S"),
+            self.current_phase,
+            self.current_algorithm.name(),
+            self.sorting_params.sort_mode.name(),
+            self.sorting_params.threshold,
+            self.sorting_params.hue_shift,
+            self.tint_enabled,
+            self.sorting_params.color_tint,
+            self.iteration_counter,
+        );
+        std::fs::write(session_dir.join("session_state.toml"), state)?;
+
+        Ok(Some(session_dir))
     }
 
-    #[cfg(target_os = "linux")]
-    fn copy_directory<P: AsRef<std::path::Path>>(src: P, dst: P) -> Result<(), Box<dyn std::error::Error>> {
-        let src = src.as_ref();
-        let dst = dst.as_ref();
-        
-        if !src.exists() {
-            return Err("Source directory does not exist".into());
+    /// Copy the files chosen in the export dialog to the drive in the background, then eject it
+    pub fn start_usb_export(&mut self, drive: &RemovableDrive) {
+        if !drive.writable {
+            self.export_message = Some(format!("✗ {} is read-only", drive.label));
+            self.export_message_time = Some(Instant::now());
+            return;
+        }
+        // Images still being written wouldn't be in the export, or could be deleted half-exported
+        self.poll_image_writes();
+        if self.image_writer.pending() > 0 {
+            self.export_message = Some(format!("✗ Still saving {} image(s) - try again in a moment", self.image_writer.pending()));
+            self.export_message_time = Some(Instant::now());
+            return;
+        }
+        // Time-lapse frames are saved and read back for the animation by their own threads
+        if self.burst.is_some() || self.timelapse.is_some() || self.timelapse_result.is_some() {
+            self.export_message = Some("✗ Still capturing - try again when it has finished".to_string());
+            self.export_message_time = Some(Instant::now());
+            return;
+        }
+
+        let destination = drive.mount_point.join(&self.config.export.folder_name);
+        log::info!("Exporting {} to {} ({}, delete_after: {})", self.config.paths.output_dir.display(),
+                   destination.display(), drive.device, self.usb_export_delete_after);
+        self.usb_export_job = Some(UsbExportJob::start(ExportTarget {
+            source: self.config.paths.output_dir.clone(),
+            destination,
+            device: Some(drive.device.clone()),
+            mount_point: drive.mount_point.clone(),
+            delete_source: self.usb_export_delete_after,
+            files: self.usb_export_selected.clone(),
+            zip_per_session: self.usb_export_zip,
+        }));
+        self.usb_export_label = drive.label.clone();
+        self.usb_export_outcome = None;
+    }
+
+    /// Report the end of a background export
+    pub fn poll_usb_export(&mut self) {
+        let Some(job) = &self.usb_export_job else {
+            return;
+        };
+        let progress = job.progress();
+        if !progress.status.is_done() {
+            return;
         }
+        self.usb_export_job = None;
 
-        std::fs::create_dir_all(dst)?;
+        let outcome = match progress.status {
+            ExportStatus::Finished { ejected } => {
+                let mut outcome = if progress.mismatches.is_empty() {
+                    let unit = if self.usb_export_zip { "archives" } else { "files" };
+                    format!("✓ Exported and verified {} {}", progress.files_verified, unit)
+                } else {
+                    format!("⚠ {} of {} files failed verification and were kept locally: {}",
+                            progress.mismatches.len(), progress.files_total, summarize_files(&progress.mismatches))
+                };
+                outcome.push_str(&format!("\n{} new, {} updated, {} already on the drive",
+                                          progress.files_new, progress.files_updated, progress.files_skipped));
 
-        for entry in std::fs::read_dir(src)? {
-            let entry = entry?;
-            let src_path = entry.path();
-            let dst_path = dst.join(entry.file_name());
+                // Only verified files were deleted by the export job
+                if self.usb_export_delete_after {
+                    log::info!("Deleted {} verified local files after export", progress.files_deleted);
+                    outcome.push_str(&format!("\nDeleted {} local copies", progress.files_deleted));
+                    if progress.files_deleted > 0 {
+                        // The session folder may be gone; start a new one on the next save
+                        self.current_session_folder = None;
+                        self.iteration_counter = 0;
+                    }
+                }
 
-            if src_path.is_dir() {
-                // Recursively copy subdirectories (session folders)
-                Self::copy_directory(&src_path, &dst_path)?;
-            } else if src_path.is_file() {
-                std::fs::copy(&src_path, &dst_path)?;
+                if ejected {
+                    format!("{}\n{} is safe to remove", outcome, self.usb_export_label)
+                } else {
+                    format!("{}\nCould not eject {} - wait a moment before removing it", outcome, self.usb_export_label)
+                }
             }
-        }
+            ExportStatus::Cancelled => format!(
+                "✗ Export cancelled after {} of {} files\nWait a moment before removing {}",
+                progress.files_done, progress.files_total, self.usb_export_label
+            ),
+            ExportStatus::Failed(e) => format!("✗ Export failed: {}", e),
+            _ => unreachable!("export still running"),
+        };
 
-        Ok(())
+        self.usb_export_delete_after = self.config.export.delete_after_copy; // Reset for next time
+        self.reset_export_selection();
+        self.export_message = outcome.lines().next().map(str::to_string);
+        self.export_message_time = Some(Instant::now());
+        self.usb_export_outcome = Some(outcome);
     }
 
     pub fn start_new_photo_session(&mut self) {
@@ -151,47 +262,64 @@ impl PixelSorterApp {
         }
     }
 
-    pub fn load_last_iteration_as_source(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        if let Some(ref session_folder) = self.current_session_folder {
-            if self.iteration_counter > 0 {
-                // Load the last saved iteration as the new source
-                let session_dir = PathBuf::from("sorted_images").join(session_folder);
-                
-                // Find the last saved file
-                let iteration_prefix = format!("edit_{:03}_", self.iteration_counter);
-                for entry in std::fs::read_dir(&session_dir)? {
-                    let entry = entry?;
-                    let filename = entry.file_name().to_string_lossy().to_string();
-                    if filename.starts_with(&iteration_prefix) {
-                        // Load this image as the new original
-                        let image_path = entry.path();
-                        match image::open(&image_path) {
-                            Ok(img) => {
-                                let rgb_image = img.to_rgb8();
-                                self.original_image = Some(rgb_image);
-                                return Ok(());
-                            }
-                            Err(e) => return Err(e.into()),
-                        }
-                    }
-                }
+    /// Queue the current edit for saving and keep editing it as the new source.
+    /// The image is encoded and written in the background; failures are reported by poll_image_writes.
+    pub fn save_and_continue_iteration(&mut self, ctx: &egui::Context) {
+        let Some(processed) = self.processed_image.clone() else {
+            return;
+        };
+
+        let algorithm = self.current_algorithm;
+        match self.next_iteration_request(processed.clone(), &algorithm) {
+            Ok(request) => {
+                // The number is taken when the write is queued, not when it lands, so writes still
+                // in flight never share one. A failed write keeps its number (the toast names the
+                // missing file) rather than renumbering saves that may already be on disk.
+                self.image_writer.queue(request);
+                self.iteration_counter += 1;
+
+                // The in-memory result is the next source, no need to read it back from disk
+                self.original_image = Some(processed);
+                self.apply_pixel_sort(ctx);
+            }
+            Err(e) => {
+                log::error!("Failed to create session folder: {}", e);
+                self.export_message = Some(format!("✗ Save failed: {}", e));
+                self.export_message_time = Some(Instant::now());
+                self.recheck_disk_usage();
             }
         }
-        Err("No previous iteration found to load".into())
     }
 
-    pub fn save_and_continue_iteration(&mut self, ctx: &egui::Context) {
-        if let Some(ref processed) = self.processed_image.clone() {
-            // Extract algorithm to avoid borrow conflict
-            let algorithm = self.current_algorithm;
-            // Save the current iteration using the existing auto-save system
-            if let Ok(_saved_path) = self.auto_save_image(processed, &algorithm) {
-                // Load the saved image as the new source for next iteration
-                if let Ok(()) = self.load_last_iteration_as_source() {
-                    // Process the loaded image immediately for preview
-                    self.apply_pixel_sort(ctx);
-                }
-            }
-        }
+    /// Report iterations whose background write failed (usually a full disk)
+    pub fn poll_image_writes(&mut self) {
+        let failed: Vec<_> = self
+            .image_writer
+            .take_results()
+            .into_iter()
+            .filter_map(|write| write.result.err().map(|e| (write.path, e)))
+            .collect();
+        let Some((path, error)) = failed.last() else {
+            return;
+        };
+
+        let file_name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+        self.export_message = Some(if failed.len() > 1 {
+            format!("✗ {} saves failed: {}", failed.len(), error)
+        } else {
+            format!("✗ Save failed ({}): {}", file_name, error)
+        });
+        self.export_message_time = Some(Instant::now());
+        self.recheck_disk_usage();
+    }
+}
+/// "a, b, c and 4 more" for listing files in a message
+fn summarize_files(files: &[String]) -> String {
+    const SHOWN: usize = 3;
+    let listed = files.iter().take(SHOWN).cloned().collect::<Vec<_>>().join(", ");
+    if files.len() > SHOWN {
+        format!("{} and {} more", listed, files.len() - SHOWN)
+    } else {
+        listed
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,8 @@
 // Session domain - session and state management
 
+pub mod import;
+pub mod library;
 pub mod manager;
+pub mod retention;
+pub mod usb_export;
+pub mod writer;
@@ -0,0 +1,186 @@
+// Retention rules for the output folder: limit the number, age and total size of sessions.
+// Exported sessions are pruned first, oldest first; unexported ones only when allowed.
+
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use super::library;
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Limits on saved sessions; 0 disables a limit
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct RetentionRules {
+    pub max_sessions: usize,
+    pub max_age_days: u64,
+    pub max_total_bytes: u64,
+    /// Also delete sessions that were never exported when a limit is still exceeded
+    pub prune_unexported: bool,
+}
+
+impl RetentionRules {
+    pub fn is_enabled(&self) -> bool {
+        self.max_sessions > 0 || self.max_age_days > 0 || self.max_total_bytes > 0
+    }
+}
+
+/// Size and age of one session folder
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionUsage {
+    pub name: String,
+    pub bytes: u64,
+    /// Newest file modification time, in seconds since the Unix epoch
+    pub last_modified: u64,
+    pub exported: bool,
+}
+
+/// Usage of every session_* folder in the output folder
+pub fn session_usage(output_dir: &Path) -> Vec<SessionUsage> {
+    let Ok(entries) = std::fs::read_dir(output_dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .flatten()
+        .filter(|entry| entry.path().is_dir() && entry.file_name().to_string_lossy().starts_with("session_"))
+        .map(|entry| {
+            let dir = entry.path();
+            let mut bytes = 0;
+            let mut last_modified = 0;
+            for file in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+                let Ok(metadata) = file.metadata() else {
+                    continue;
+                };
+                bytes += metadata.len();
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map_or(0, |duration| duration.as_secs());
+                last_modified = last_modified.max(modified);
+            }
+            SessionUsage {
+                name: entry.file_name().to_string_lossy().to_string(),
+                bytes,
+                last_modified,
+                exported: library::is_exported(&dir),
+            }
+        })
+        .collect()
+}
+
+/// Sessions to delete so the rules hold. `keep` (the session being edited) is never chosen.
+pub fn sessions_to_prune(sessions: &[SessionUsage], rules: &RetentionRules, now: u64, keep: Option<&str>) -> Vec<String> {
+    if !rules.is_enabled() {
+        return Vec::new();
+    }
+
+    let mut candidates: Vec<&SessionUsage> = sessions
+        .iter()
+        .filter(|session| Some(session.name.as_str()) != keep)
+        .filter(|session| session.exported || rules.prune_unexported)
+        .collect();
+    // Exported before unexported, oldest first within each
+    candidates.sort_by_key(|session| (!session.exported, session.last_modified, session.name.clone()));
+
+    let mut remaining_count = sessions.len();
+    let mut remaining_bytes: u64 = sessions.iter().map(|session| session.bytes).sum();
+    let mut pruned = Vec::new();
+    for session in candidates {
+        let too_many = rules.max_sessions > 0 && remaining_count > rules.max_sessions;
+        let too_big = rules.max_total_bytes > 0 && remaining_bytes > rules.max_total_bytes;
+        let too_old = rules.max_age_days > 0 && now.saturating_sub(session.last_modified) > rules.max_age_days * SECS_PER_DAY;
+        if too_many || too_big || too_old {
+            remaining_count -= 1;
+            remaining_bytes -= session.bytes;
+            pruned.push(session.name.clone());
+        }
+    }
+    pruned
+}
+
+/// Delete the named session folders. Returns how many were removed and the bytes freed.
+pub fn prune_sessions(output_dir: &Path, sessions: &[SessionUsage], names: &[String]) -> (usize, u64) {
+    let mut removed = 0;
+    let mut freed = 0;
+    for session in sessions.iter().filter(|session| names.contains(&session.name)) {
+        match std::fs::remove_dir_all(output_dir.join(&session.name)) {
+            Ok(()) => {
+                log::info!("Pruned session {} ({} bytes, exported: {})", session.name, session.bytes, session.exported);
+                removed += 1;
+                freed += session.bytes;
+            }
+            Err(e) => log::warn!("Could not prune session {}: {}", session.name, e),
+        }
+    }
+    (removed, freed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, bytes: u64, age_days: u64, exported: bool) -> SessionUsage {
+        SessionUsage {
+            name: name.to_string(),
+            bytes,
+            last_modified: 100 * SECS_PER_DAY - age_days * SECS_PER_DAY,
+            exported,
+        }
+    }
+
+    #[test]
+    fn test_sessions_to_prune() {
+        let now = 100 * SECS_PER_DAY;
+        let sessions = vec![
+            session("session_a", 300, 40, false),
+            session("session_b", 200, 30, true),
+            session("session_c", 100, 20, true),
+            session("session_d", 100, 1, false),
+        ];
+
+        assert!(sessions_to_prune(&sessions, &RetentionRules::default(), now, None).is_empty());
+
+        // Exported sessions go first, oldest first; the unexported one is kept
+        let count = RetentionRules { max_sessions: 2, ..Default::default() };
+        assert_eq!(sessions_to_prune(&sessions, &count, now, None), vec!["session_b", "session_c"]);
+        let count = RetentionRules { max_sessions: 1, ..Default::default() };
+        assert_eq!(sessions_to_prune(&sessions, &count, now, None), vec!["session_b", "session_c"]);
+        let count = RetentionRules { max_sessions: 1, prune_unexported: true, ..Default::default() };
+        assert_eq!(sessions_to_prune(&sessions, &count, now, Some("session_d")), vec!["session_b", "session_c", "session_a"]);
+
+        let size = RetentionRules { max_total_bytes: 500, ..Default::default() };
+        assert_eq!(sessions_to_prune(&sessions, &size, now, None), vec!["session_b"]);
+
+        let age = RetentionRules { max_age_days: 25, ..Default::default() };
+        assert_eq!(sessions_to_prune(&sessions, &age, now, None), vec!["session_b"]);
+        let age = RetentionRules { max_age_days: 25, prune_unexported: true, ..Default::default() };
+        assert_eq!(sessions_to_prune(&sessions, &age, now, None), vec!["session_b", "session_a"]);
+    }
+
+    #[test]
+    fn test_prune_removes_folders() {
+        let temp = tempfile::tempdir().unwrap();
+        let output = temp.path();
+        std::fs::create_dir_all(output.join("session_old")).unwrap();
+        std::fs::create_dir_all(output.join("session_new")).unwrap();
+        std::fs::write(output.join("session_old/edit_001.png"), vec![0u8; 1000]).unwrap();
+        std::fs::write(output.join("session_new/edit_001.png"), vec![0u8; 10]).unwrap();
+        library::mark_exported(&output.join("session_old"), &["edit_001.png".to_string()], std::time::SystemTime::now()).unwrap();
+
+        let usage = session_usage(output);
+        assert_eq!(usage.len(), 2);
+        let old = usage.iter().find(|session| session.name == "session_old").unwrap();
+        assert!(old.exported);
+        assert!(old.bytes >= 1000);
+
+        let rules = RetentionRules { max_total_bytes: 500, ..Default::default() };
+        let now = usage.iter().map(|session| session.last_modified).max().unwrap();
+        let names = sessions_to_prune(&usage, &rules, now, None);
+        assert_eq!(names, vec!["session_old"]);
+        assert_eq!(prune_sessions(output, &usage, &names).0, 1);
+        assert!(!output.join("session_old").exists());
+        assert!(output.join("session_new").exists());
+
+    }
+}
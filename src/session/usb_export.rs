@@ -0,0 +1,968 @@
+// Background USB export: copies the output folder to a drive, fsyncs it, verifies
+// every copy against a SHA-256 of its source and ejects the drive.
+// A manifest on the drive lets later exports copy only new or changed files.
+// Sessions can optionally be packed into one ZIP archive each.
+// Progress is shared with the UI through a mutex; cancelling stops between chunks
+
+use std::collections::{BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::UNIX_EPOCH;
+use sha2::{Digest, Sha256};
+
+use super::library;
+
+const COPY_CHUNK_BYTES: usize = 256 * 1024;
+/// Export record kept inside the export folder on the drive
+const MANIFEST_FILE: &str = ".pixelsort_manifest";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExportStatus {
+    Copying,
+    /// Flushing directories to the drive
+    Syncing,
+    /// Re-reading copies and comparing checksums
+    Verifying,
+    /// Removing local files whose copies verified
+    Deleting,
+    Ejecting,
+    /// Copy finished; `ejected` is false if the drive could not be unmounted
+    Finished { ejected: bool },
+    Cancelled,
+    Failed(String),
+}
+
+impl ExportStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(self, ExportStatus::Finished { .. } | ExportStatus::Cancelled | ExportStatus::Failed(_))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportProgress {
+    pub status: ExportStatus,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Path of the file being copied, relative to the output folder
+    pub current_file: String,
+    /// Copied because they weren't on the drive yet
+    pub files_new: usize,
+    /// Copied again because they changed since the last export
+    pub files_updated: usize,
+    /// Already on the drive and unchanged
+    pub files_skipped: usize,
+    pub files_verified: usize,
+    /// Files whose copy didn't match the source (kept locally)
+    pub mismatches: Vec<String>,
+    pub files_deleted: usize,
+}
+
+impl Default for ExportProgress {
+    fn default() -> Self {
+        Self {
+            status: ExportStatus::Copying,
+            files_done: 0,
+            files_total: 0,
+            bytes_done: 0,
+            bytes_total: 0,
+            current_file: String::new(),
+            files_new: 0,
+            files_updated: 0,
+            files_skipped: 0,
+            files_verified: 0,
+            mismatches: Vec::new(),
+            files_deleted: 0,
+        }
+    }
+}
+
+impl ExportProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.bytes_total == 0 {
+            return if self.status == ExportStatus::Copying { 0.0 } else { 1.0 };
+        }
+        self.bytes_done as f32 / self.bytes_total as f32
+    }
+}
+
+/// Where to export and which device to eject afterwards
+pub struct ExportTarget {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// Block device to unmount and power off (None skips ejecting)
+    pub device: Option<String>,
+    pub mount_point: PathBuf,
+    /// Delete source files once their copy has verified
+    pub delete_source: bool,
+    /// Files to export, relative to `source` (None exports everything)
+    pub files: Option<BTreeSet<PathBuf>>,
+    /// Pack each session folder into <session>.zip instead of copying loose files
+    pub zip_per_session: bool,
+}
+
+/// A running export; drop it only after it has finished
+pub struct UsbExportJob {
+    progress: Arc<Mutex<ExportProgress>>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl UsbExportJob {
+    pub fn start(target: ExportTarget) -> Self {
+        let progress = Arc::new(Mutex::new(ExportProgress::default()));
+        let cancel = Arc::new(AtomicBool::new(false));
+
+        let thread_progress = progress.clone();
+        let thread_cancel = cancel.clone();
+        std::thread::spawn(move || {
+            let status = run_export(&target, &thread_progress, &thread_cancel);
+            match &status {
+                ExportStatus::Finished { ejected } => log::info!("USB export finished (ejected: {})", ejected),
+                ExportStatus::Cancelled => log::info!("USB export cancelled"),
+                ExportStatus::Failed(e) => log::error!("USB export failed: {}", e),
+                _ => {}
+            }
+            set_status(&thread_progress, status);
+        });
+
+        Self { progress, cancel }
+    }
+
+    pub fn progress(&self) -> ExportProgress {
+        self.progress.lock().map(|p| p.clone()).unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    /// Stop copying or verifying; files already copied stay on the drive, the partial one is
+    /// removed, and nothing local is deleted
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+fn set_status(progress: &Mutex<ExportProgress>, status: ExportStatus) {
+    if let Ok(mut progress) = progress.lock() {
+        progress.status = status;
+    }
+}
+
+fn run_export(target: &ExportTarget, progress: &Mutex<ExportProgress>, cancel: &AtomicBool) -> ExportStatus {
+    if !target.source.is_dir() {
+        return ExportStatus::Failed("Nothing to export yet".to_string());
+    }
+
+    // Files saved after this point aren't in the export, whatever their timestamps
+    let listed_at = std::time::SystemTime::now();
+    let mut files = match list_files(&target.source) {
+        Ok(files) => files,
+        Err(e) => return ExportStatus::Failed(format!("Cannot read {}: {}", target.source.display(), e)),
+    };
+    let all_files: Vec<PathBuf> = files.iter().map(|file| file.relative.clone()).collect();
+    if let Some(selected) = &target.files {
+        files.retain(|file| selected.contains(&file.relative));
+    }
+    if files.is_empty() {
+        return ExportStatus::Failed("No files selected".to_string());
+    }
+    let units = export_units(files, target.zip_per_session, &all_files);
+
+    // Work out what the drive already has from the previous export's manifest
+    let mut manifest = Manifest::load(&target.destination);
+    let plan: Vec<FileChange> = units
+        .iter()
+        .map(|unit| manifest.change_for(unit, &target.destination.join(&unit.relative)))
+        .collect();
+    if let Ok(mut progress) = progress.lock() {
+        progress.files_total = units.len();
+        progress.bytes_total = units
+            .iter()
+            .zip(&plan)
+            .filter(|(_, change)| **change != FileChange::Unchanged)
+            .map(|(unit, _)| unit.size)
+            .sum();
+    }
+
+    let mut stopped = None;
+    for (unit, change) in units.iter().zip(&plan) {
+        if *change == FileChange::Unchanged {
+            if let Ok(mut progress) = progress.lock() {
+                progress.files_done += 1;
+                progress.files_skipped += 1;
+            }
+            continue;
+        }
+
+        if let Ok(mut progress) = progress.lock() {
+            progress.current_file = unit.relative.display().to_string();
+        }
+
+        let destination = target.destination.join(&unit.relative);
+        let result = if unit.archive {
+            write_archive(&target.source, unit, &destination, progress, cancel)
+        } else {
+            copy_file(&target.source.join(&unit.relative), &destination, progress, cancel)
+        };
+        match result {
+            Ok(Some(checksum)) => manifest.record(unit, checksum),
+            Ok(None) => {
+                let _ = std::fs::remove_file(&destination);
+                stopped = Some(ExportStatus::Cancelled);
+                break;
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&destination);
+                stopped = Some(ExportStatus::Failed(format!("{}: {}", unit.relative.display(), e)));
+                break;
+            }
+        }
+
+        if let Ok(mut progress) = progress.lock() {
+            progress.files_done += 1;
+            match change {
+                FileChange::New => progress.files_new += 1,
+                _ => progress.files_updated += 1,
+            }
+        }
+    }
+
+    // Saved even after a cancel, so the files that made it are skipped next time
+    if let Err(e) = manifest.save(&target.destination) {
+        log::warn!("Could not write export manifest: {}", e);
+    }
+    if let Some(status) = stopped {
+        return status;
+    }
+
+    // File contents are already synced; make the new directory entries durable too
+    set_status(progress, ExportStatus::Syncing);
+    let mut directories: Vec<PathBuf> = units
+        .iter()
+        .filter_map(|unit| unit.relative.parent().map(|parent| target.destination.join(parent)))
+        .collect();
+    directories.sort();
+    directories.dedup();
+    directories.push(target.mount_point.clone());
+    for directory in directories {
+        if let Err(e) = File::open(&directory).and_then(|dir| dir.sync_all()) {
+            log::debug!("Could not sync {}: {}", directory.display(), e);
+        }
+    }
+
+    // Read every copy back (including skipped ones) and compare with the source's checksum.
+    // The copy's cached pages are dropped first, so the bytes come from the drive itself.
+    set_status(progress, ExportStatus::Verifying);
+    let mut verified = Vec::with_capacity(units.len());
+    for (unit, change) in units.iter().zip(&plan) {
+        if cancel.load(Ordering::Relaxed) {
+            return ExportStatus::Cancelled;
+        }
+
+        let relative = &unit.relative;
+        let expected = manifest.checksum(relative);
+        let copy = target.destination.join(relative);
+        evict_cached_pages(&copy);
+        let actual = if unit.archive { hash_archive(&copy) } else { hash_file(&copy) };
+        // A skipped unit's checksum is from an earlier export, and size and mtime can match
+        // after a rewrite; before deleting, check the source still has those contents
+        let source_matches = !(target.delete_source && *change == FileChange::Unchanged)
+            || hash_source(&target.source, unit).is_ok_and(|checksum| Some(checksum.as_str()) == expected);
+        match actual {
+            Ok(copy_checksum) if Some(copy_checksum.as_str()) == expected && source_matches => verified.extend(&unit.members),
+            result => {
+                let reason = result.err().map(|e| e.to_string()).unwrap_or_else(|| "checksum mismatch".to_string());
+                log::warn!("Export verification failed for {}: {}", relative.display(), reason);
+                if let Ok(mut progress) = progress.lock() {
+                    progress.mismatches.push(relative.display().to_string());
+                }
+                continue;
+            }
+        }
+        if let Ok(mut progress) = progress.lock() {
+            progress.files_verified += 1;
+        }
+    }
+
+    if target.delete_source {
+        set_status(progress, ExportStatus::Deleting);
+        delete_verified(&target.source, &verified, progress);
+    } else {
+        mark_exported_sessions(&target.source, &all_files, &verified, listed_at);
+    }
+
+    let Some(device) = &target.device else {
+        return ExportStatus::Finished { ejected: false };
+    };
+    set_status(progress, ExportStatus::Ejecting);
+    ExportStatus::Finished { ejected: eject(device, &target.mount_point) }
+}
+
+/// A file in the output folder
+#[derive(Debug, Clone)]
+struct SourceFile {
+    /// Path relative to the output folder
+    relative: PathBuf,
+    size: u64,
+    /// Modification time in seconds since the Unix epoch
+    modified: u64,
+}
+
+/// All files under `root`, sorted by path
+fn list_files(root: &Path) -> std::io::Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    let mut pending = vec![PathBuf::new()];
+    while let Some(relative) = pending.pop() {
+        for entry in std::fs::read_dir(root.join(&relative))? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            let path = relative.join(entry.file_name());
+            // Markers like .exported stay local
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            if metadata.is_dir() {
+                pending.push(path);
+            } else if metadata.is_file() {
+                let modified = metadata
+                    .modified()
+                    .ok()
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                    .map(|age| age.as_secs())
+                    .unwrap_or(0);
+                files.push(SourceFile { relative: path, size: metadata.len(), modified });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(files)
+}
+
+/// Mark session folders whose files all verified, so storage pruning can remove them first
+fn mark_exported_sessions(source: &Path, all_files: &[PathBuf], verified: &[&PathBuf], listed_at: std::time::SystemTime) {
+    let session_of = |file: &Path| {
+        let mut components = file.components();
+        let session = components.next()?;
+        components.next().map(|_| PathBuf::from(session.as_os_str()))
+    };
+
+    let sessions: BTreeSet<PathBuf> = verified.iter().filter_map(|file| session_of(file)).collect();
+    for session in sessions {
+        let session_files: Vec<&PathBuf> = all_files
+            .iter()
+            .filter(|file| session_of(file).as_ref() == Some(&session))
+            .filter(|file| file.file_name().is_some_and(|name| name != library::FAVOURITES_FILE))
+            .collect();
+        if session_files.iter().all(|file| verified.contains(file)) {
+            let names: Vec<String> = session_files
+                .iter()
+                .filter_map(|file| file.file_name())
+                .map(|name| name.to_string_lossy().to_string())
+                .collect();
+            if let Err(e) = library::mark_exported(&source.join(&session), &names, listed_at) {
+                log::warn!("Could not mark {} as exported: {}", session.display(), e);
+            }
+        }
+    }
+}
+
+/// One file written to the drive: a copy of a source file, or a session archive
+#[derive(Debug, Clone)]
+struct ExportUnit {
+    /// Path relative to the export folder
+    relative: PathBuf,
+    /// Total size of the source files
+    size: u64,
+    /// Newest modification time of the source files
+    modified: u64,
+    /// Source files it contains, relative to the output folder
+    members: Vec<PathBuf>,
+    archive: bool,
+}
+
+/// Group files into what gets written: one unit per file, or one archive per session folder.
+/// An archive of part of a session (finals, favourites, a hand-picked selection) gets its own
+/// name, so it never replaces a fuller archive of the same session from an earlier export.
+fn export_units(files: Vec<SourceFile>, zip_per_session: bool, all_files: &[PathBuf]) -> Vec<ExportUnit> {
+    let mut units: Vec<ExportUnit> = Vec::new();
+    for file in files {
+        let session = file.relative.parent().filter(|parent| !parent.as_os_str().is_empty());
+        match session.filter(|_| zip_per_session) {
+            Some(session) => {
+                let relative = session.with_extension("zip");
+                match units.iter_mut().find(|unit| unit.relative == relative) {
+                    Some(unit) => {
+                        unit.size += file.size;
+                        unit.modified = unit.modified.max(file.modified);
+                        unit.members.push(file.relative);
+                    }
+                    None => units.push(ExportUnit {
+                        relative,
+                        size: file.size,
+                        modified: file.modified,
+                        members: vec![file.relative],
+                        archive: true,
+                    }),
+                }
+            }
+            None => units.push(ExportUnit {
+                relative: file.relative.clone(),
+                size: file.size,
+                modified: file.modified,
+                members: vec![file.relative],
+                archive: false,
+            }),
+        }
+    }
+
+    for unit in units.iter_mut().filter(|unit| unit.archive) {
+        let session = unit.relative.with_extension("");
+        let session_files = all_files
+            .iter()
+            .filter(|file| file.parent() == Some(session.as_path()))
+            .filter(|file| file.file_name().is_some_and(|name| name != library::FAVOURITES_FILE))
+            .count();
+        if unit.members.len() < session_files {
+            let names: Vec<String> = unit.members.iter().map(|member| member.display().to_string()).collect();
+            let selection = to_hex(&Sha256::digest(names.join("\n").as_bytes()));
+            unit.relative = PathBuf::from(format!("{}_{}.zip", session.display(), &selection[..8]));
+        }
+    }
+    units
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FileChange {
+    New,
+    Updated,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ManifestEntry {
+    size: u64,
+    modified: u64,
+    /// SHA-256 of the source, hex encoded
+    checksum: String,
+}
+
+/// Record of what was exported into a folder, kept next to the files on the drive
+/// so it travels with the drive. One line per file: checksum, size, mtime, path.
+#[derive(Debug, Default)]
+struct Manifest {
+    entries: HashMap<PathBuf, ManifestEntry>,
+}
+
+impl Manifest {
+    /// Read the manifest in `destination` (empty if there is none or it is unreadable)
+    fn load(destination: &Path) -> Self {
+        let contents = std::fs::read_to_string(destination.join(MANIFEST_FILE)).unwrap_or_default();
+        let entries = contents
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(4, '\t');
+                let checksum = fields.next()?.to_string();
+                let size = fields.next()?.parse().ok()?;
+                let modified = fields.next()?.parse().ok()?;
+                let path = PathBuf::from(fields.next()?);
+                Some((path, ManifestEntry { size, modified, checksum }))
+            })
+            .collect();
+        Self { entries }
+    }
+
+    fn save(&self, destination: &Path) -> std::io::Result<()> {
+        let mut paths: Vec<&PathBuf> = self.entries.keys().collect();
+        paths.sort();
+        let contents: String = paths
+            .into_iter()
+            .map(|path| {
+                let entry = &self.entries[path];
+                format!("{}\t{}\t{}\t{}\n", entry.checksum, entry.size, entry.modified, path.display())
+            })
+            .collect();
+
+        std::fs::create_dir_all(destination)?;
+        let temp_path = destination.join(format!("{}.tmp", MANIFEST_FILE));
+        let mut file = File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        std::fs::rename(&temp_path, destination.join(MANIFEST_FILE))
+    }
+
+    /// Same size and mtime as last time, with the copy still on the drive, counts as unchanged
+    fn change_for(&self, unit: &ExportUnit, copy: &Path) -> FileChange {
+        match self.entries.get(&unit.relative) {
+            None => FileChange::New,
+            Some(entry) => {
+                // Archives are a little bigger than their contents, so only check they exist
+                let copy_size = std::fs::metadata(copy).map(|m| if unit.archive { unit.size } else { m.len() }).ok();
+                if entry.size == unit.size && entry.modified == unit.modified && copy_size == Some(unit.size) {
+                    FileChange::Unchanged
+                } else {
+                    FileChange::Updated
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, unit: &ExportUnit, checksum: String) {
+        self.entries.insert(unit.relative.clone(), ManifestEntry { size: unit.size, modified: unit.modified, checksum });
+    }
+
+    fn checksum(&self, relative: &Path) -> Option<&str> {
+        self.entries.get(relative).map(|entry| entry.checksum.as_str())
+    }
+}
+
+/// Copy in chunks so progress updates and cancelling stay responsive on slow drives.
+/// Returns the source's SHA-256, or None if cancelled.
+fn copy_file(source: &Path, destination: &Path, progress: &Mutex<ExportProgress>, cancel: &AtomicBool) -> std::io::Result<Option<String>> {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut input = File::open(source)?;
+    let mut output = File::create(destination)?;
+    let mut buffer = vec![0u8; COPY_CHUNK_BYTES];
+    let mut hasher = Sha256::new();
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Ok(None);
+        }
+
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        output.write_all(&buffer[..read])?;
+
+        if let Ok(mut progress) = progress.lock() {
+            progress.bytes_done += read as u64;
+        }
+    }
+
+    output.sync_all()?;
+    Ok(Some(to_hex(&hasher.finalize())))
+}
+
+/// Write a ZIP (stored, since images are already compressed) of the unit's files.
+/// Returns the archive checksum from `hash_archive`'s scheme, or None if cancelled.
+fn write_archive(
+    source: &Path,
+    unit: &ExportUnit,
+    destination: &Path,
+    progress: &Mutex<ExportProgress>,
+    cancel: &AtomicBool,
+) -> std::io::Result<Option<String>> {
+    if let Some(parent) = destination.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let mut archive = zip::ZipWriter::new(File::create(destination)?);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .large_file(unit.size > u32::MAX as u64);
+    let mut buffer = vec![0u8; COPY_CHUNK_BYTES];
+    let mut member_checksums = Vec::with_capacity(unit.members.len());
+
+    for member in &unit.members {
+        let name = member.to_string_lossy().replace('\\', "/");
+        archive.start_file(name, options).map_err(std::io::Error::other)?;
+
+        let mut input = File::open(source.join(member))?;
+        let mut hasher = Sha256::new();
+        loop {
+            if cancel.load(Ordering::Relaxed) {
+                return Ok(None);
+            }
+
+            let read = input.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+            archive.write_all(&buffer[..read])?;
+
+            if let Ok(mut progress) = progress.lock() {
+                progress.bytes_done += read as u64;
+            }
+        }
+        member_checksums.push(to_hex(&hasher.finalize()));
+    }
+
+    archive.finish().map_err(std::io::Error::other)?.sync_all()?;
+    Ok(Some(combine_checksums(&member_checksums)))
+}
+
+/// Checksum of an archive's contents: every member is read back (which also checks
+/// its CRC) and hashed, then the member hashes are hashed together in order
+fn hash_archive(path: &Path) -> std::io::Result<String> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?).map_err(std::io::Error::other)?;
+    let mut buffer = vec![0u8; COPY_CHUNK_BYTES];
+    let mut member_checksums = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let mut member = archive.by_index(index).map_err(std::io::Error::other)?;
+        let mut hasher = Sha256::new();
+        loop {
+            let read = member.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            hasher.update(&buffer[..read]);
+        }
+        member_checksums.push(to_hex(&hasher.finalize()));
+    }
+    Ok(combine_checksums(&member_checksums))
+}
+
+/// Checksum of a unit's source files, computed the same way as while copying them
+fn hash_source(source: &Path, unit: &ExportUnit) -> std::io::Result<String> {
+    if !unit.archive {
+        return hash_file(&source.join(&unit.relative));
+    }
+    let member_checksums = unit
+        .members
+        .iter()
+        .map(|member| hash_file(&source.join(member)))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    Ok(combine_checksums(&member_checksums))
+}
+
+fn combine_checksums(checksums: &[String]) -> String {
+    let mut hasher = Sha256::new();
+    for checksum in checksums {
+        hasher.update(checksum.as_bytes());
+    }
+    to_hex(&hasher.finalize())
+}
+
+/// Ask the kernel to drop a synced file's pages from the page cache, so the next read hits the device
+#[cfg(unix)]
+fn evict_cached_pages(path: &Path) {
+    use std::os::unix::io::AsRawFd;
+
+    let Ok(file) = File::open(path) else {
+        return;
+    };
+    // Pages still dirty can't be dropped; sync them before advising
+    let _ = file.sync_all();
+    // SAFETY: the descriptor is open for the duration of the call
+    let result = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+    if result != 0 {
+        log::debug!("Could not drop cached pages of {}: error {}", path.display(), result);
+    }
+}
+
+#[cfg(not(unix))]
+fn evict_cached_pages(_path: &Path) {}
+
+fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut buffer = vec![0u8; COPY_CHUNK_BYTES];
+    let mut hasher = Sha256::new();
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(to_hex(&hasher.finalize()));
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Remove verified source files, then any folders left empty (deepest first)
+fn delete_verified(source: &Path, verified: &[&PathBuf], progress: &Mutex<ExportProgress>) {
+    let mut directories = Vec::new();
+    for relative in verified {
+        match std::fs::remove_file(source.join(relative)) {
+            Ok(()) => {
+                if let Ok(mut progress) = progress.lock() {
+                    progress.files_deleted += 1;
+                }
+            }
+            Err(e) => log::warn!("Could not delete {}: {}", relative.display(), e),
+        }
+        directories.extend(relative.ancestors().skip(1).map(|dir| source.join(dir)));
+    }
+
+    directories.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+    directories.dedup();
+    for directory in directories {
+        // Fails (and is left alone) while unverified files remain inside
+        let _ = std::fs::remove_dir(directory);
+    }
+}
+
+/// Unmount the drive, then power it off if udisks is available
+fn eject(device: &str, mount_point: &Path) -> bool {
+    let run = |program: &str, args: &[&str]| {
+        Command::new(program)
+            .args(args)
+            .output()
+            .map(|output| {
+                if !output.status.success() {
+                    log::warn!("{} {}: {}", program, args.join(" "), String::from_utf8_lossy(&output.stderr).trim());
+                }
+                output.status.success()
+            })
+            .unwrap_or(false)
+    };
+
+    let mount_point = mount_point.to_string_lossy();
+    let unmounted = run("udisksctl", &["unmount", "-b", device]) || run("umount", &[&mount_point]);
+    if unmounted && !run("udisksctl", &["power-off", "-b", device]) {
+        log::info!("{} unmounted but not powered off", device);
+    }
+    unmounted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_progress() -> Mutex<ExportProgress> {
+        Mutex::new(ExportProgress::default())
+    }
+
+    #[test]
+    fn test_export_copies_everything() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::create_dir_all(source.join("session_1")).unwrap();
+        std::fs::write(source.join("session_1/edit_001.png"), vec![7u8; 300_000]).unwrap();
+        std::fs::write(source.join("notes.txt"), "hello").unwrap();
+        let drive_dir = tempfile::tempdir().unwrap();
+        let drive = drive_dir.path().to_path_buf();
+
+        let target = ExportTarget {
+            source: source.clone(),
+            destination: drive.join("pixelsort_export"),
+            device: None,
+            mount_point: drive.clone(),
+            delete_source: false,
+            files: None,
+            zip_per_session: false,
+        };
+        let progress = new_progress();
+        let status = run_export(&target, &progress, &AtomicBool::new(false));
+
+        assert_eq!(status, ExportStatus::Finished { ejected: false });
+        let progress = progress.into_inner().unwrap();
+        assert_eq!((progress.files_done, progress.files_total), (2, 2));
+        assert_eq!(progress.files_verified, 2);
+        assert_eq!(progress.files_new, 2);
+        assert!(progress.mismatches.is_empty());
+        assert_eq!(progress.bytes_done, 300_005);
+        assert_eq!(std::fs::read(drive.join("pixelsort_export/session_1/edit_001.png")).unwrap().len(), 300_000);
+        assert_eq!(std::fs::read_to_string(drive.join("pixelsort_export/notes.txt")).unwrap(), "hello");
+        assert!(library::is_exported(&source.join("session_1")));
+
+    }
+
+    #[test]
+    fn test_incremental_export() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::write(source.join("a.png"), "first").unwrap();
+        std::fs::write(source.join("b.png"), "second").unwrap();
+        let drive_dir = tempfile::tempdir().unwrap();
+        let drive = drive_dir.path().to_path_buf();
+
+        let target = ExportTarget {
+            source: source.clone(),
+            destination: drive.join("export"),
+            device: None,
+            mount_point: drive.clone(),
+            delete_source: false,
+            files: None,
+            zip_per_session: false,
+        };
+        let export = || {
+            let progress = new_progress();
+            assert_eq!(run_export(&target, &progress, &AtomicBool::new(false)), ExportStatus::Finished { ejected: false });
+            let progress = progress.into_inner().unwrap();
+            (progress.files_new, progress.files_updated, progress.files_skipped, progress.files_verified)
+        };
+
+        assert_eq!(export(), (2, 0, 0, 2));
+        assert_eq!(export(), (0, 0, 2, 2));
+
+        std::fs::write(source.join("b.png"), "second, edited").unwrap();
+        std::fs::write(source.join("c.png"), "third").unwrap();
+        assert_eq!(export(), (1, 1, 1, 3));
+        assert_eq!(std::fs::read_to_string(drive.join("export/b.png")).unwrap(), "second, edited");
+
+        // A copy deleted from the drive is sent again
+        std::fs::remove_file(drive.join("export/a.png")).unwrap();
+        assert_eq!(export(), (0, 1, 2, 3));
+
+    }
+
+    #[test]
+    fn test_skipped_files_are_rehashed_before_delete() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::write(source.join("a.png"), "first").unwrap();
+        std::fs::write(source.join("b.png"), "other").unwrap();
+        let drive_dir = tempfile::tempdir().unwrap();
+        let drive = drive_dir.path().to_path_buf();
+
+        let mut target = ExportTarget {
+            source: source.clone(),
+            destination: drive.join("export"),
+            device: None,
+            mount_point: drive.clone(),
+            delete_source: false,
+            files: None,
+            zip_per_session: false,
+        };
+        assert_eq!(run_export(&target, &new_progress(), &AtomicBool::new(false)), ExportStatus::Finished { ejected: false });
+
+        // Rewritten with the same size and timestamp, so the manifest still calls it unchanged
+        let modified = std::fs::metadata(source.join("a.png")).unwrap().modified().unwrap();
+        std::fs::write(source.join("a.png"), "FIRST").unwrap();
+        File::options().write(true).open(source.join("a.png")).unwrap().set_modified(modified).unwrap();
+
+        target.delete_source = true;
+        let progress = new_progress();
+        assert_eq!(run_export(&target, &progress, &AtomicBool::new(false)), ExportStatus::Finished { ejected: false });
+        let progress = progress.into_inner().unwrap();
+        assert_eq!(progress.files_skipped, 2);
+        assert_eq!(progress.mismatches, vec!["a.png".to_string()]);
+        assert_eq!(std::fs::read_to_string(source.join("a.png")).unwrap(), "FIRST");
+        assert!(!source.join("b.png").exists());
+
+    }
+
+    #[test]
+    fn test_zip_per_session_with_selection() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::create_dir_all(source.join("session_1")).unwrap();
+        std::fs::create_dir_all(source.join("session_2")).unwrap();
+        std::fs::write(source.join("session_1/edit_001.png"), "one").unwrap();
+        std::fs::write(source.join("session_1/edit_002.png"), "two").unwrap();
+        std::fs::write(source.join("session_2/edit_001.png"), "skipped").unwrap();
+        let drive_dir = tempfile::tempdir().unwrap();
+        let drive = drive_dir.path().to_path_buf();
+
+        let target = ExportTarget {
+            source: source.clone(),
+            destination: drive.join("export"),
+            device: None,
+            mount_point: drive.clone(),
+            delete_source: true,
+            files: Some(BTreeSet::from([PathBuf::from("session_1/edit_001.png"), PathBuf::from("session_1/edit_002.png")])),
+            zip_per_session: true,
+        };
+        let progress = new_progress();
+        assert_eq!(run_export(&target, &progress, &AtomicBool::new(false)), ExportStatus::Finished { ejected: false });
+        let progress = progress.into_inner().unwrap();
+        assert_eq!((progress.files_total, progress.files_verified, progress.files_deleted), (1, 1, 2));
+
+        let mut archive = zip::ZipArchive::new(File::open(drive.join("export/session_1.zip")).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive.by_name("session_1/edit_002.png").unwrap().read_to_string(&mut contents).unwrap();
+        assert_eq!(contents, "two");
+        assert_eq!(archive.len(), 2);
+        assert!(!drive.join("export/session_2").exists());
+        assert!(!source.join("session_1").exists());
+        assert!(source.join("session_2/edit_001.png").exists());
+
+    }
+
+    #[test]
+    fn test_partial_archive_keeps_full_archive() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::create_dir_all(source.join("session_1")).unwrap();
+        std::fs::write(source.join("session_1/edit_001.png"), "one").unwrap();
+        std::fs::write(source.join("session_1/edit_002.png"), "two").unwrap();
+        let drive_dir = tempfile::tempdir().unwrap();
+        let drive = drive_dir.path().to_path_buf();
+
+        let export = |files: Option<BTreeSet<PathBuf>>| {
+            let target = ExportTarget {
+                source: source.clone(),
+                destination: drive.join("export"),
+                device: None,
+                mount_point: drive.clone(),
+                delete_source: false,
+                files,
+                zip_per_session: true,
+            };
+            run_export(&target, &new_progress(), &AtomicBool::new(false))
+        };
+        assert_eq!(export(None), ExportStatus::Finished { ejected: false });
+        // Finals only: a separate, smaller archive next to the full one
+        assert_eq!(
+            export(Some(BTreeSet::from([PathBuf::from("session_1/edit_002.png")]))),
+            ExportStatus::Finished { ejected: false }
+        );
+
+        let full = zip::ZipArchive::new(File::open(drive.join("export/session_1.zip")).unwrap()).unwrap();
+        assert_eq!(full.len(), 2);
+        let archives: Vec<String> = std::fs::read_dir(drive.join("export"))
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("session_1_") && name.ends_with(".zip"))
+            .collect();
+        assert_eq!(archives.len(), 1, "{:?}", archives);
+        let partial = zip::ZipArchive::new(File::open(drive.join("export").join(&archives[0])).unwrap()).unwrap();
+        assert_eq!(partial.len(), 1);
+
+    }
+
+    #[test]
+    fn test_cancel_removes_partial_file() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::write(source.join("big.png"), vec![1u8; 100_000]).unwrap();
+        let drive_dir = tempfile::tempdir().unwrap();
+        let drive = drive_dir.path().to_path_buf();
+
+        let target = ExportTarget {
+            source: source.clone(),
+            destination: drive.join("export"),
+            device: None,
+            mount_point: drive.clone(),
+            delete_source: false,
+            files: None,
+            zip_per_session: false,
+        };
+        let status = run_export(&target, &new_progress(), &AtomicBool::new(true));
+
+        assert_eq!(status, ExportStatus::Cancelled);
+        assert!(!drive.join("export/big.png").exists());
+
+    }
+
+    #[test]
+    fn test_only_verified_files_are_deleted() {
+        let source_dir = tempfile::tempdir().unwrap();
+        let source = source_dir.path().to_path_buf();
+        std::fs::create_dir_all(source.join("session_1")).unwrap();
+        std::fs::create_dir_all(source.join("session_2")).unwrap();
+        std::fs::write(source.join("session_1/edit_001.png"), "good").unwrap();
+        std::fs::write(source.join("session_2/edit_001.png"), "good").unwrap();
+        std::fs::write(source.join("session_2/edit_002.png"), "bad").unwrap();
+
+        let verified = [PathBuf::from("session_1/edit_001.png"), PathBuf::from("session_2/edit_001.png")];
+        let progress = new_progress();
+        delete_verified(&source, &verified.iter().collect::<Vec<_>>(), &progress);
+
+        assert_eq!(progress.into_inner().unwrap().files_deleted, 2);
+        assert!(!source.join("session_1").exists());
+        assert!(!source.join("session_2/edit_001.png").exists());
+        assert!(source.join("session_2/edit_002.png").exists());
+
+    }
+}
@@ -0,0 +1,220 @@
+// Background writer for saved iterations and burst frames: encoding and writing run on a worker thread,
+// so Save & Iterate never waits for the disk. Writes happen in the order they were queued,
+// and each one reports back through a channel the UI polls every frame.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, TryRecvError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use image::RgbImage;
+
+use crate::processing::output_format::{self, OutputFormat};
+
+/// Size and quality of the small JPEG saved next to an iteration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShareCopy {
+    pub max_size: u32,
+    pub quality: u8,
+}
+
+pub struct WriteRequest {
+    pub image: RgbImage,
+    pub path: PathBuf,
+    pub format: OutputFormat,
+    pub share_copy: Option<ShareCopy>,
+}
+
+/// Outcome of one queued write
+#[derive(Debug)]
+pub struct WriteResult {
+    pub path: PathBuf,
+    pub result: Result<(), String>,
+}
+
+/// Encode and write one iteration. A failed share copy is only logged; the master is what matters.
+pub fn write_iteration(request: &WriteRequest) -> anyhow::Result<()> {
+    write_in_place(&request.path, |temp| output_format::save_image(&request.image, temp, request.format))?;
+
+    if let Some(share) = request.share_copy {
+        let share_path = output_format::share_copy_path(&request.path);
+        let saved = write_in_place(&share_path, |temp| {
+            output_format::save_share_copy(&request.image, temp, share.max_size, share.quality)
+        });
+        if let Err(e) = saved {
+            log::warn!("Could not save share copy {}: {}", share_path.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Hidden name next to `path` that a file is written under before it's renamed into place
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+    path.with_file_name(format!(".{}.tmp", name))
+}
+
+/// Run `save` on a hidden temp file and rename it to `path`, so an export or the library
+/// never sees a half-written image. Exports skip hidden files.
+pub fn write_in_place(path: &Path, save: impl FnOnce(&Path) -> anyhow::Result<()>) -> anyhow::Result<()> {
+    let temp = temp_path(path);
+    let result = save(&temp).and_then(|()| Ok(std::fs::rename(&temp, path)?));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp);
+    }
+    result
+}
+
+pub struct ImageWriter {
+    requests: Option<Sender<WriteRequest>>,
+    results: Receiver<WriteResult>,
+    worker: Option<JoinHandle<()>>,
+    /// Paths of writes queued but not yet finished, oldest first
+    pending: Vec<PathBuf>,
+}
+
+impl ImageWriter {
+    pub fn new() -> Self {
+        let (request_sender, requests) = mpsc::channel::<WriteRequest>();
+        let (result_sender, results) = mpsc::channel();
+
+        let worker = std::thread::spawn(move || {
+            for request in requests {
+                let started = Instant::now();
+                let result = write_iteration(&request).map_err(|e| e.to_string());
+                match &result {
+                    Ok(()) => log::info!("Saved {} in {:?}", request.path.display(), started.elapsed()),
+                    Err(e) => log::error!("Failed to save {}: {}", request.path.display(), e),
+                }
+                let _ = result_sender.send(WriteResult { path: request.path, result });
+            }
+        });
+
+        Self {
+            requests: Some(request_sender),
+            results,
+            worker: Some(worker),
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn queue(&mut self, request: WriteRequest) {
+        let path = request.path.clone();
+        let sent = self.requests.as_ref().is_some_and(|requests| requests.send(request).is_ok());
+        if sent {
+            self.pending.push(path);
+        } else {
+            log::error!("Image writer stopped, could not save {}", path.display());
+        }
+    }
+
+    /// Writes queued but not yet finished
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Files that will appear once the queued writes finish
+    pub fn pending_paths(&self) -> &[PathBuf] {
+        &self.pending
+    }
+
+    fn finished(&mut self, result: &WriteResult) {
+        if let Some(index) = self.pending.iter().position(|path| *path == result.path) {
+            self.pending.remove(index);
+        }
+    }
+
+    /// Results of writes that finished since the last call
+    pub fn take_results(&mut self) -> Vec<WriteResult> {
+        let mut finished = Vec::new();
+        loop {
+            match self.results.try_recv() {
+                Ok(result) => finished.push(result),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.pending.clear();
+                    break;
+                }
+            }
+        }
+        for result in &finished {
+            self.finished(result);
+        }
+        finished
+    }
+
+    /// Block until every queued write finished or `timeout` passed, e.g. before a shutdown
+    pub fn wait_idle(&mut self, timeout: Duration) -> Vec<WriteResult> {
+        let deadline = Instant::now() + timeout;
+        let mut finished = Vec::new();
+        while !self.pending.is_empty() {
+            match self.results.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(result) => {
+                    self.finished(&result);
+                    finished.push(result);
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => self.pending.clear(),
+            }
+        }
+        finished
+    }
+}
+
+impl Default for ImageWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for ImageWriter {
+    /// Finish queued writes when the app closes
+    fn drop(&mut self) {
+        self.requests = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_writes_in_background() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let image = RgbImage::from_pixel(40, 20, image::Rgb([10, 200, 30]));
+        let png = OutputFormat::Png(image::codecs::png::CompressionType::Fast);
+
+        let mut writer = ImageWriter::new();
+        writer.queue(WriteRequest {
+            image: image.clone(),
+            path: dir.join("edit_001_horizontal.png"),
+            format: png,
+            share_copy: Some(ShareCopy { max_size: 10, quality: 80 }),
+        });
+        writer.queue(WriteRequest {
+            image: image.clone(),
+            path: dir.join("missing/edit_002_vertical.png"),
+            format: png,
+            share_copy: None,
+        });
+        assert_eq!(writer.pending(), 2);
+        assert_eq!(writer.pending_paths()[1], dir.join("missing/edit_002_vertical.png"));
+
+        let results = writer.wait_idle(Duration::from_secs(10));
+        assert_eq!(writer.pending(), 0);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].result.is_ok());
+        assert!(results[1].result.is_err(), "writing into a missing folder should fail");
+        assert_eq!(image::open(dir.join("edit_001_horizontal.png")).unwrap().to_rgb8(), image);
+        assert!(dir.join("edit_001_horizontal_share.jpg").exists());
+        let left_over: Vec<_> = std::fs::read_dir(dir).unwrap().flatten()
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with('.'))
+            .collect();
+        assert!(left_over.is_empty(), "temp files should be renamed into place");
+        assert!(writer.take_results().is_empty());
+
+    }
+}
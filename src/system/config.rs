@@ -0,0 +1,874 @@
+// Application configuration loaded from pixelsort.toml
+// Every key has a default, so the file only needs the settings that differ.
+// Any key can be overridden with an environment variable: PIXELSORT_<SECTION>_<KEY>,
+// e.g. PIXELSORT_DISPLAY_SLEEP_TIMEOUT_SECS=60 or PIXELSORT_UPS_ENABLED=true.
+// The settings screen writes only the keys it changed back with save_edits().
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use crate::hardware::{CameraBackendKind, CameraConfig, DischargeCurve, GaugeChip, Rotation, UpsConfig};
+use crate::processing::output_format::{self, OutputFormat};
+use crate::session::library::ExportMode;
+use crate::session::retention::RetentionRules;
+
+pub const CONFIG_FILE: &str = "pixelsort.toml";
+const ENV_PREFIX: &str = "PIXELSORT_";
+/// Overrides the config file location
+const ENV_CONFIG_PATH: &str = "PIXELSORT_CONFIG";
+const SECTIONS: &[&str] = &["paths", "display", "camera", "ups", "export", "storage", "output", "update"];
+/// Low-battery countdown limits: long enough to save the user's work, short enough not to drain the battery
+pub const MIN_WARNING_SECS: u64 = 5;
+pub const MAX_WARNING_SECS: u64 = 300;
+/// Largest [ups] soc_hysteresis, in percent
+pub const MAX_SOC_HYSTERESIS: f32 = 10.0;
+/// Largest [camera] lens_k1 either way; beyond this the correction folds the image edges over
+pub const MAX_LENS_K1: f32 = 0.5;
+/// Allowed [output] share_max_size, in pixels
+pub const MIN_SHARE_SIZE: u32 = 64;
+pub const MAX_SHARE_SIZE: u32 = 4096;
+/// Separate files used before pixelsort.toml existed, still read for sections it doesn't define
+const LEGACY_FILES: &[(&str, &str)] = &[("ups", "ups_config.toml"), ("camera", "camera_config.toml")];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub paths: PathsConfig,
+    pub display: DisplayConfig,
+    pub camera: CameraSection,
+    pub ups: UpsSection,
+    pub export: ExportConfig,
+    pub storage: StorageConfig,
+    pub output: OutputConfig,
+    pub update: UpdateConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    /// Where session folders are saved
+    pub output_dir: PathBuf,
+    /// Folders offered by the image browser, besides output_dir
+    pub image_folders: Vec<PathBuf>,
+    /// Battery sample log used for the runtime estimate
+    pub battery_history: PathBuf,
+}
+
+impl Default for PathsConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("sorted_images"),
+            image_folders: vec![PathBuf::from("/home/pixelsort/Pictures")],
+            battery_history: PathBuf::from("battery_history.csv"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    #[serde(serialize_with = "short_float")]
+    pub splash_secs: f32,
+    /// Idle time before the sleep screen (0 = never sleep)
+    pub sleep_timeout_secs: u64,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        Self {
+            width: 1920,
+            height: 1080,
+            fullscreen: true,
+            splash_secs: 2.0,
+            sleep_timeout_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CameraSection {
+    /// "libcamera" or "gphoto2"
+    pub backend: String,
+    pub gphoto2_binary: String,
+    /// Degrees clockwise: 0, 90, 180 or 270
+    pub rotation: u32,
+    pub flip_horizontal: bool,
+    pub flip_vertical: bool,
+    #[serde(serialize_with = "short_float")]
+    pub lens_k1: f32,
+    pub capture_width: u32,
+    pub capture_height: u32,
+    pub preview_width: u32,
+    pub preview_height: u32,
+    /// JPEG quality for still capture (1-100)
+    pub quality: u8,
+}
+
+impl Default for CameraSection {
+    fn default() -> Self {
+        let camera = CameraConfig::default();
+        Self {
+            backend: "libcamera".to_string(),
+            gphoto2_binary: camera.gphoto2_binary,
+            rotation: 0,
+            flip_horizontal: false,
+            flip_vertical: false,
+            lens_k1: 0.0,
+            capture_width: camera.capture_width,
+            capture_height: camera.capture_height,
+            preview_width: camera.preview_width,
+            preview_height: camera.preview_height,
+            quality: camera.quality,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpsSection {
+    pub enabled: bool,
+    pub i2c_bus: u8,
+    pub i2c_address: u8,
+    /// auto, ina219, max17043, max17048, max17049, sysfs or fake
+    pub chip: String,
+    #[serde(serialize_with = "short_float")]
+    pub voltage_threshold: f32,
+    pub check_interval_secs: u64,
+    pub warning_duration_secs: u64,
+    #[serde(serialize_with = "short_float")]
+    pub fake_voltage: f32,
+    pub fake_charging: bool,
+    pub power_gpio: Option<u8>,
+    pub power_gpio_active_low: bool,
+    /// liion_<n>s or lifepo4_<n>s
+    pub battery_profile: String,
+    /// "voltage:percent" pairs, overrides battery_profile
+    pub battery_curve: Option<String>,
+    #[serde(serialize_with = "short_float")]
+    pub soc_hysteresis: f32,
+}
+
+impl Default for UpsSection {
+    fn default() -> Self {
+        let ups = UpsConfig::default();
+        Self {
+            enabled: ups.enabled,
+            i2c_bus: ups.i2c_bus,
+            i2c_address: ups.i2c_address,
+            chip: "auto".to_string(),
+            voltage_threshold: ups.voltage_threshold,
+            check_interval_secs: ups.check_interval_secs,
+            warning_duration_secs: ups.warning_duration_secs,
+            fake_voltage: ups.fake_voltage,
+            fake_charging: ups.fake_charging,
+            power_gpio: ups.power_gpio,
+            power_gpio_active_low: ups.power_gpio_active_low,
+            battery_profile: "liion_2s".to_string(),
+            battery_curve: None,
+            soc_hysteresis: ups.soc_hysteresis,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExportConfig {
+    /// Folder created on the USB drive
+    pub folder_name: String,
+    /// Initial state of the "delete after copying" checkbox
+    pub delete_after_copy: bool,
+    /// Default export mode: "everything", "finals" or "favourites"
+    pub mode: String,
+    /// Initial state of the "ZIP archive per session" checkbox
+    pub zip_per_session: bool,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            folder_name: "pixelsort_export".to_string(),
+            delete_after_copy: false,
+            mode: "everything".to_string(),
+            zip_per_session: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageConfig {
+    /// Warn when free space on the output folder's disk drops below this
+    pub low_space_mb: u64,
+    /// Retention limits (0 = no limit); exported sessions are removed first
+    pub max_sessions: usize,
+    pub max_age_days: u64,
+    pub max_total_mb: u64,
+    /// Also remove sessions that were never exported when a limit is exceeded
+    pub prune_unexported: bool,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            low_space_mb: 500,
+            max_sessions: 0,
+            max_age_days: 0,
+            max_total_mb: 0,
+            prune_unexported: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Format of saved iterations: "png", "jpeg", "webp" (lossless) or "tiff"
+    pub format: String,
+    /// PNG compression: "fast", "default" or "best"
+    pub png_compression: String,
+    /// JPEG quality, 1-100
+    pub jpeg_quality: u8,
+    /// Also save a small JPEG next to every iteration for sharing
+    pub share_copy: bool,
+    /// Longest side of the share copy in pixels
+    pub share_max_size: u32,
+    pub share_quality: u8,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            format: "png".to_string(),
+            png_compression: "fast".to_string(),
+            jpeg_quality: 90,
+            share_copy: false,
+            share_max_size: 1600,
+            share_quality: 80,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct UpdateConfig {
+    /// Git checkout the app updates itself from
+    pub install_dir: String,
+    /// systemd service restarted after pulling an update
+    pub service_name: String,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            install_dir: "/home/pixelsort/Pixelsort".to_string(),
+            service_name: "pixelsort-kiosk".to_string(),
+        }
+    }
+}
+
+impl AppConfig {
+    /// Load pixelsort.toml (or $PIXELSORT_CONFIG), legacy files and environment overrides.
+    /// A missing file gives the defaults; an invalid one is an error describing what is wrong.
+    pub fn load() -> Result<AppConfig, String> {
+        let (table, _) = Self::load_table(false)?;
+        Self::from_table(table)
+    }
+
+    /// Startup variant of load(): a section that doesn't parse or validate falls back to its
+    /// defaults and the others are kept, so a typo in [display] never turns off the UPS.
+    /// Returns the problems found, if any, for the UI to report.
+    pub fn load_with_fallback() -> (AppConfig, Option<String>) {
+        let (table, mut problems) = Self::load_table(true).unwrap_or_default();
+        let config = match Self::from_table(table.clone()) {
+            Ok(config) => config,
+            Err(_) => {
+                let (config, section_problems) = Self::from_table_per_section(table);
+                problems.extend(section_problems);
+                config
+            }
+        };
+        (config, (!problems.is_empty()).then(|| problems.join("; ")))
+    }
+
+    /// The merged TOML from all sources. When `lenient`, unreadable parts are skipped
+    /// and reported instead of failing the load.
+    fn load_table(lenient: bool) -> Result<(toml::Table, Vec<String>), String> {
+        let path = config_path();
+        let mut problems = Vec::new();
+
+        let mut table = match read_table(Path::new(&path)) {
+            Ok(Some(table)) => {
+                log::info!("Configuration loaded from {}", path);
+                table
+            }
+            Ok(None) => {
+                log::info!("No config found at {}, using defaults", path);
+                toml::Table::new()
+            }
+            Err(e) if lenient => {
+                let (table, section_problems) = parse_sections(&std::fs::read_to_string(&path).unwrap_or_default());
+                log::warn!("Configuration partly loaded from {}", path);
+                problems.push(e);
+                problems.extend(section_problems.into_iter().map(|problem| format!("{}: {}", path, problem)));
+                table
+            }
+            Err(e) => return Err(e),
+        };
+
+        for (section, legacy_path) in LEGACY_FILES {
+            if table.contains_key(*section) {
+                continue;
+            }
+            let legacy = match read_table(Path::new(legacy_path)) {
+                Ok(legacy) => legacy,
+                Err(e) if lenient => {
+                    problems.push(e);
+                    None
+                }
+                Err(e) => return Err(e),
+            };
+            if let Some(value) = legacy.and_then(|mut legacy| legacy.remove(*section)) {
+                log::warn!("Reading [{}] from {} - consider moving it into {}", section, legacy_path, path);
+                table.insert(section.to_string(), value);
+            }
+        }
+
+        match apply_env_overrides(&mut table, std::env::vars()) {
+            Ok(()) => {}
+            Err(e) if lenient => problems.push(e),
+            Err(e) => return Err(e),
+        }
+        Ok((table, problems))
+    }
+
+    /// Write the settings screen's changes to pixelsort.toml (or $PIXELSORT_CONFIG).
+    /// `self` is the config in effect; only the keys `edited` changes are written, on top of
+    /// what the file already holds, so environment overrides and legacy files stay out of it.
+    /// Comments in an existing file are not kept.
+    pub fn save_edits(&self, edited: &AppConfig) -> Result<(), String> {
+        edited.validate()?;
+        let path = PathBuf::from(config_path());
+        let mut file = read_table(&path)?.unwrap_or_default();
+        apply_edits(&mut file, &self.to_table()?, &edited.to_table()?);
+        Self::from_table(file.clone()).map_err(|e| format!("{} would be invalid: {}", path.display(), e))?;
+
+        let contents = format!(
+            "# Harpy configuration - saved from the settings screen\n\
+             # See pixelsort.toml.template for what each key does\n\n{}",
+            toml::to_string_pretty(&file).map_err(|e| e.to_string())?
+        );
+
+        // Write then rename, so the file watcher never sees a half-written file
+        let temp_path = path.with_extension("toml.tmp");
+        std::fs::write(&temp_path, contents)
+            .and_then(|()| std::fs::rename(&temp_path, &path))
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        log::info!("Configuration saved to {}", path.display());
+        Ok(())
+    }
+
+    fn to_table(&self) -> Result<toml::Table, String> {
+        match toml::Value::try_from(self).map_err(|e| e.to_string())? {
+            toml::Value::Table(table) => Ok(table),
+            _ => Err("Configuration is not a table".to_string()),
+        }
+    }
+
+    fn from_table(table: toml::Table) -> Result<AppConfig, String> {
+        let config: AppConfig = toml::Value::Table(table).try_into().map_err(|e| e.to_string().trim().to_string())?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Keep the sections that parse and validate on their own; the others use their defaults
+    fn from_table_per_section(table: toml::Table) -> (AppConfig, Vec<String>) {
+        let mut valid = toml::Table::new();
+        let mut problems = Vec::new();
+        for (section, value) in table {
+            let single = toml::Table::from_iter([(section.clone(), value.clone())]);
+            match Self::from_table(single) {
+                Ok(_) => {
+                    valid.insert(section, value);
+                }
+                Err(e) => {
+                    log::error!("Invalid [{}] section, using its defaults: {}", section, e);
+                    problems.push(format!("{} - [{}] uses defaults", e, section));
+                }
+            }
+        }
+        // Sections are validated independently, so the valid ones together are valid too
+        let config = Self::from_table(valid).unwrap_or_else(|e| {
+            problems.push(e);
+            AppConfig::default()
+        });
+        (config, problems)
+    }
+
+    /// Check values that parse but make no sense, reporting all problems at once
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+
+        if self.display.width == 0 || self.display.height == 0 {
+            problems.push("[display] width and height must be greater than 0".to_string());
+        }
+        if self.display.splash_secs < 0.0 {
+            problems.push("[display] splash_secs cannot be negative".to_string());
+        }
+
+        let camera = &self.camera;
+        if camera_backend(&camera.backend).is_none() {
+            problems.push(format!("[camera] backend '{}' must be libcamera or gphoto2", camera.backend));
+        }
+        if Rotation::from_degrees(camera.rotation).is_none() {
+            problems.push(format!("[camera] rotation {} must be 0, 90, 180 or 270", camera.rotation));
+        }
+        if camera.capture_width == 0 || camera.capture_height == 0 || camera.preview_width == 0 || camera.preview_height == 0 {
+            problems.push("[camera] capture and preview sizes must be greater than 0".to_string());
+        }
+        if !(1..=100).contains(&camera.quality) {
+            problems.push(format!("[camera] quality {} must be between 1 and 100", camera.quality));
+        }
+        if !camera.lens_k1.is_finite() {
+            problems.push(format!("[camera] lens_k1 {} must be a number", camera.lens_k1));
+        }
+
+        let export_folder = self.export.folder_name.trim();
+        if export_folder.is_empty() || export_folder.contains(['/', '\\']) || export_folder.starts_with('.') {
+            problems.push(format!("[export] folder_name '{}' must be a plain folder name", self.export.folder_name));
+        }
+        if ExportMode::from_name(&self.export.mode).is_none() {
+            problems.push(format!("[export] mode '{}' must be everything, finals or favourites", self.export.mode));
+        }
+
+        let output = &self.output;
+        if OutputFormat::from_config(&output.format, "fast", output.jpeg_quality).is_none() {
+            problems.push(format!("[output] format '{}' must be png, jpeg, webp or tiff", output.format));
+        }
+        if output_format::png_compression_type(&output.png_compression).is_none() {
+            problems.push(format!("[output] png_compression '{}' must be fast, default or best", output.png_compression));
+        }
+        if !(1..=100).contains(&output.jpeg_quality) || !(1..=100).contains(&output.share_quality) {
+            problems.push("[output] jpeg_quality and share_quality must be between 1 and 100".to_string());
+        }
+        if !(MIN_SHARE_SIZE..=MAX_SHARE_SIZE).contains(&output.share_max_size) {
+            problems.push(format!("[output] share_max_size {} must be between {} and {}",
+                                  output.share_max_size, MIN_SHARE_SIZE, MAX_SHARE_SIZE));
+        }
+
+        let ups = &self.ups;
+        if GaugeChip::from_name(&ups.chip).is_none() {
+            problems.push(format!("[ups] unknown chip '{}'", ups.chip));
+        }
+        if ups.i2c_address > 0x7F {
+            problems.push(format!("[ups] i2c_address 0x{:02X} is not a 7-bit I2C address", ups.i2c_address));
+        }
+        if ups.check_interval_secs == 0 {
+            problems.push("[ups] check_interval_secs must be at least 1".to_string());
+        }
+        // NaN fails every comparison, so these are written to reject it too
+        if !(ups.voltage_threshold.is_finite() && ups.voltage_threshold > 0.0) {
+            problems.push(format!("[ups] voltage_threshold {} must be a positive voltage", ups.voltage_threshold));
+        }
+        if !(MIN_WARNING_SECS..=MAX_WARNING_SECS).contains(&ups.warning_duration_secs) {
+            problems.push(format!("[ups] warning_duration_secs {} must be between {} and {}",
+                                  ups.warning_duration_secs, MIN_WARNING_SECS, MAX_WARNING_SECS));
+        }
+        if !(0.0..=MAX_SOC_HYSTERESIS).contains(&ups.soc_hysteresis) {
+            problems.push(format!("[ups] soc_hysteresis {} must be between 0 and {}", ups.soc_hysteresis, MAX_SOC_HYSTERESIS));
+        }
+        match &ups.battery_curve {
+            Some(curve) => {
+                if let Err(e) = DischargeCurve::parse(curve) {
+                    problems.push(format!("[ups] battery_curve: {}", e));
+                }
+            }
+            None => {
+                if DischargeCurve::from_profile(&ups.battery_profile).is_none() {
+                    problems.push(format!("[ups] unknown battery_profile '{}'", ups.battery_profile));
+                }
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+
+    pub fn camera_config(&self) -> CameraConfig {
+        let camera = &self.camera;
+        let mut config = CameraConfig {
+            backend: camera_backend(&camera.backend).unwrap_or_default(),
+            gphoto2_binary: camera.gphoto2_binary.clone(),
+            capture_width: camera.capture_width,
+            capture_height: camera.capture_height,
+            preview_width: camera.preview_width,
+            preview_height: camera.preview_height,
+            quality: camera.quality,
+            ..CameraConfig::default()
+        };
+        config.transform.rotation = Rotation::from_degrees(camera.rotation).unwrap_or(Rotation::None);
+        config.transform.flip_horizontal = camera.flip_horizontal;
+        config.transform.flip_vertical = camera.flip_vertical;
+        config.transform.lens_k1 = camera.lens_k1.clamp(-MAX_LENS_K1, MAX_LENS_K1);
+        config
+    }
+
+    pub fn export_mode(&self) -> ExportMode {
+        ExportMode::from_name(&self.export.mode).unwrap_or(ExportMode::Everything)
+    }
+
+    pub fn output_format(&self) -> OutputFormat {
+        let output = &self.output;
+        OutputFormat::from_config(&output.format, &output.png_compression, output.jpeg_quality)
+            .unwrap_or(OutputFormat::Png(image::codecs::png::CompressionType::Fast))
+    }
+
+    pub fn retention_rules(&self) -> RetentionRules {
+        let storage = &self.storage;
+        RetentionRules {
+            max_sessions: storage.max_sessions,
+            max_age_days: storage.max_age_days,
+            max_total_bytes: storage.max_total_mb * 1024 * 1024,
+            prune_unexported: storage.prune_unexported,
+        }
+    }
+
+    pub fn ups_config(&self) -> UpsConfig {
+        let ups = &self.ups;
+        let discharge_curve = match &ups.battery_curve {
+            Some(curve) => DischargeCurve::parse(curve).ok(),
+            None => DischargeCurve::from_profile(&ups.battery_profile),
+        };
+
+        UpsConfig {
+            enabled: ups.enabled,
+            i2c_bus: ups.i2c_bus,
+            i2c_address: ups.i2c_address,
+            voltage_threshold: ups.voltage_threshold,
+            check_interval_secs: ups.check_interval_secs,
+            warning_duration_secs: ups.warning_duration_secs,
+            chip: GaugeChip::from_name(&ups.chip).unwrap_or(GaugeChip::Auto),
+            fake_voltage: ups.fake_voltage,
+            fake_charging: ups.fake_charging,
+            power_gpio: ups.power_gpio,
+            power_gpio_active_low: ups.power_gpio_active_low,
+            discharge_curve: discharge_curve.unwrap_or_default(),
+            soc_hysteresis: ups.soc_hysteresis,
+            history_file: self.paths.battery_history.clone(),
+        }
+    }
+}
+
+/// Location of the config file: $PIXELSORT_CONFIG or pixelsort.toml in the working directory
+pub fn config_path() -> String {
+    std::env::var(ENV_CONFIG_PATH).unwrap_or_else(|_| CONFIG_FILE.to_string())
+}
+
+/// Notices when the config file (or a legacy file) is created, edited or removed
+pub struct ConfigWatcher {
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        let paths = std::iter::once(PathBuf::from(config_path()))
+            .chain(LEGACY_FILES.iter().map(|(_, path)| PathBuf::from(path)));
+        Self {
+            files: paths.map(|path| {
+                let modified = modified_time(&path);
+                (path, modified)
+            }).collect(),
+        }
+    }
+
+    /// True once for each change since the last call
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+        for (path, last_modified) in &mut self.files {
+            let modified = modified_time(path);
+            if modified != *last_modified {
+                *last_modified = modified;
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn camera_backend(name: &str) -> Option<CameraBackendKind> {
+    match name {
+        "libcamera" | "rpicam" => Some(CameraBackendKind::Libcamera),
+        "gphoto2" => Some(CameraBackendKind::Gphoto2),
+        _ => None,
+    }
+}
+
+/// Write f32 values as typed ("7.4" rather than "7.400000095367432")
+fn short_float<S: serde::Serializer>(value: &f32, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(value.to_string().parse().unwrap_or(*value as f64))
+}
+
+/// Parse a TOML file (None if it doesn't exist)
+fn read_table(path: &Path) -> Result<Option<toml::Table>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => contents
+            .parse::<toml::Table>()
+            .map(Some)
+            .map_err(|e| format!("{}: {}", path.display(), e.to_string().trim())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Cannot read {}: {}", path.display(), e)),
+    }
+}
+
+/// Copy the keys that differ between `before` and `after` into `file`,
+/// removing the ones `after` no longer has (an optional setting that was cleared)
+fn apply_edits(file: &mut toml::Table, before: &toml::Table, after: &toml::Table) {
+    let empty = toml::Table::new();
+    let sections: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    for section in sections {
+        let old = before.get(section).and_then(toml::Value::as_table).unwrap_or(&empty);
+        let new = after.get(section).and_then(toml::Value::as_table).unwrap_or(&empty);
+        let changed: Vec<(&String, Option<&toml::Value>)> = old
+            .keys()
+            .chain(new.keys())
+            .filter(|key| old.get(*key) != new.get(*key))
+            .map(|key| (key, new.get(key)))
+            .collect();
+        if changed.is_empty() {
+            continue;
+        }
+
+        let Some(file_section) = file
+            .entry(section.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+        else {
+            continue;
+        };
+        for (key, value) in changed {
+            match value {
+                Some(value) => file_section.insert(key.clone(), value.clone()),
+                None => file_section.remove(key),
+            };
+        }
+    }
+}
+
+/// Parse TOML one [section] at a time, skipping sections with syntax errors
+fn parse_sections(contents: &str) -> (toml::Table, Vec<String>) {
+    let mut chunks = vec![String::new()];
+    for line in contents.lines() {
+        if is_section_header(line) {
+            chunks.push(String::new());
+        }
+        if let Some(chunk) = chunks.last_mut() {
+            chunk.push_str(line);
+            chunk.push('\n');
+        }
+    }
+
+    let mut table = toml::Table::new();
+    let mut problems = Vec::new();
+    for chunk in chunks {
+        match chunk.parse::<toml::Table>() {
+            Ok(parsed) => table.extend(parsed),
+            Err(e) => problems.push(e.to_string().trim().to_string()),
+        }
+    }
+    (table, problems)
+}
+
+/// "[ups]" or "[camera] # comment", but not an array value on its own line
+fn is_section_header(line: &str) -> bool {
+    let line = line.split('#').next().unwrap_or_default().trim();
+    line.strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .is_some_and(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')))
+}
+
+/// Apply PIXELSORT_<SECTION>_<KEY> variables on top of the file
+fn apply_env_overrides(
+    table: &mut toml::Table,
+    vars: impl Iterator<Item = (String, String)>,
+) -> Result<(), String> {
+    for (name, raw) in vars {
+        let Some(rest) = name.strip_prefix(ENV_PREFIX) else {
+            continue;
+        };
+        if name == ENV_CONFIG_PATH {
+            continue;
+        }
+
+        let rest = rest.to_lowercase();
+        let (section, key) = rest
+            .split_once('_')
+            .filter(|(section, _)| SECTIONS.contains(section))
+            .ok_or_else(|| format!("{}: expected {}<{}>_<KEY>", name, ENV_PREFIX, SECTIONS.join("|").to_uppercase()))?;
+
+        // Numbers and booleans are read as TOML; anything else is taken as a string
+        let value = format!("value = {}", raw)
+            .parse::<toml::Table>()
+            .ok()
+            .and_then(|mut parsed| parsed.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(raw.clone()));
+
+        table
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .ok_or_else(|| format!("[{}] must be a table", section))?
+            .insert(key.to_string(), value);
+        log::info!("{} overrides [{}] {}", name, section, key);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(contents: &str) -> Result<AppConfig, String> {
+        AppConfig::from_table(contents.parse::<toml::Table>().map_err(|e| e.to_string())?)
+    }
+
+    #[test]
+    fn test_defaults_and_partial_file() {
+        let config = parse("[display]\nsleep_timeout_secs = 60\n\n[ups]\nenabled = true\ni2c_address = 0x40\n").unwrap();
+        assert_eq!(config.display.sleep_timeout_secs, 60);
+        assert_eq!(config.display.width, 1920);
+        assert_eq!(config.paths.output_dir, PathBuf::from("sorted_images"));
+
+        let ups = config.ups_config();
+        assert!(ups.enabled);
+        assert_eq!(ups.i2c_address, 0x40);
+    }
+
+    #[test]
+    fn test_template_is_valid() {
+        let config = parse(include_str!("../../pixelsort.toml.template")).unwrap();
+        assert_eq!(config.display.sleep_timeout_secs, 300);
+        assert_eq!(config.ups.i2c_address, 0x36);
+    }
+
+    #[test]
+    fn test_errors_are_reported() {
+        let unknown = parse("[display]\nsleep_timeout = 60\n").unwrap_err();
+        assert!(unknown.contains("sleep_timeout"), "{}", unknown);
+
+        let invalid = parse("[camera]\nrotation = 45\nquality = 0\n").unwrap_err();
+        assert!(invalid.contains("rotation 45") && invalid.contains("quality 0"), "{}", invalid);
+
+        let lens = parse("[camera]\nlens_k1 = nan\n").unwrap_err();
+        assert!(lens.contains("lens_k1 NaN"), "{}", lens);
+        let strong = parse("[camera]\nlens_k1 = -3.0\n").unwrap();
+        assert_eq!(strong.camera_config().transform.lens_k1, -MAX_LENS_K1);
+
+        let ups = parse("[ups]\nvoltage_threshold = -1.0\nwarning_duration_secs = 0\nsoc_hysteresis = -2.0\n").unwrap_err();
+        assert!(ups.contains("voltage_threshold -1") && ups.contains("warning_duration_secs 0") && ups.contains("soc_hysteresis -2"), "{}", ups);
+    }
+
+    #[test]
+    fn test_edits_keep_file_values_only() {
+        // Effective config: the file plus an environment override of output_dir
+        let mut file = "[display]\nsleep_timeout_secs = 60\n\n[ups]\npower_gpio = 6\n".parse::<toml::Table>().unwrap();
+        let mut effective = AppConfig::from_table(file.clone()).unwrap();
+        effective.paths.output_dir = PathBuf::from("/mnt/override");
+
+        let mut edited = effective.clone();
+        edited.camera.quality = 70;
+        edited.ups.power_gpio = None;
+        apply_edits(&mut file, &effective.to_table().unwrap(), &edited.to_table().unwrap());
+
+        let saved = AppConfig::from_table(file.clone()).unwrap();
+        assert_eq!(saved.camera.quality, 70);
+        assert_eq!(saved.display.sleep_timeout_secs, 60);
+        assert_eq!(saved.ups.power_gpio, None);
+        assert_eq!(saved.paths.output_dir, PathBuf::from("sorted_images"));
+        assert!(!file.contains_key("paths"));
+    }
+
+    #[test]
+    fn test_invalid_sections_fall_back_alone() {
+        let table = "[display]\nsleep_timeout = 60\n\n[ups]\nenabled = true\n".parse::<toml::Table>().unwrap();
+        let (config, problems) = AppConfig::from_table_per_section(table);
+        assert!(config.ups.enabled);
+        assert_eq!(config.display, DisplayConfig::default());
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("[display]"), "{:?}", problems);
+
+        // A syntax error only loses its own section
+        let (table, problems) = parse_sections("[display]\nwidth = = 3\n\n[ups]\nenabled = true\n[paths]\nimage_folders = [\n\"/a\",\n]\n");
+        assert_eq!(problems.len(), 1);
+        let config = AppConfig::from_table(table).unwrap();
+        assert!(config.ups.enabled);
+        assert_eq!(config.paths.image_folders, vec![PathBuf::from("/a")]);
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        let mut table = "[display]\nsleep_timeout_secs = 60\n".parse::<toml::Table>().unwrap();
+        let vars = vec![
+            ("PIXELSORT_DISPLAY_SLEEP_TIMEOUT_SECS".to_string(), "0".to_string()),
+            ("PIXELSORT_PATHS_OUTPUT_DIR".to_string(), "/mnt/photos".to_string()),
+            ("PIXELSORT_UPS_ENABLED".to_string(), "true".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        apply_env_overrides(&mut table, vars.into_iter()).unwrap();
+
+        let config = AppConfig::from_table(table).unwrap();
+        assert_eq!(config.display.sleep_timeout_secs, 0);
+        assert_eq!(config.paths.output_dir, PathBuf::from("/mnt/photos"));
+        assert!(config.ups.enabled);
+
+        let bad = vec![("PIXELSORT_SCREEN_WIDTH".to_string(), "800".to_string())];
+        assert!(apply_env_overrides(&mut toml::Table::new(), bad.into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_save_round_trip() {
+        let mut config = AppConfig::default();
+        config.display.sleep_timeout_secs = 600;
+        config.camera.quality = 75;
+        config.ups.voltage_threshold = 6.4;
+        config.ups.power_gpio = Some(6);
+        config.export.delete_after_copy = true;
+        config.export.mode = "favourites".to_string();
+        config.export.zip_per_session = true;
+        config.paths.image_folders = vec![PathBuf::from("/srv/photos"), PathBuf::from("imports")];
+        config.storage.max_sessions = 50;
+        config.storage.max_total_mb = 2048;
+        config.output.format = "jpeg".to_string();
+        config.output.jpeg_quality = 85;
+        config.output.share_copy = true;
+
+        let saved = toml::to_string_pretty(&config.to_table().unwrap()).unwrap();
+        assert!(saved.contains("voltage_threshold = 6.4\n"), "{}", saved);
+        assert_eq!(parse(&saved).unwrap(), config);
+        assert_eq!(parse(&toml::to_string_pretty(&AppConfig::default()).unwrap()).unwrap(), AppConfig::default());
+
+        config.export.mode = "best".to_string();
+        assert!(config.validate().is_err());
+        config.export.mode = "finals".to_string();
+        assert_eq!(config.export_mode(), ExportMode::FinalsOnly);
+        assert_eq!(config.retention_rules().max_total_bytes, 2048 * 1024 * 1024);
+        assert_eq!(config.output_format(), OutputFormat::Jpeg { quality: 85 });
+        config.output.format = "gif".to_string();
+        assert!(config.validate().is_err());
+        config.output.format = "png".to_string();
+
+        config.export.folder_name = "../escape".to_string();
+        assert!(AppConfig::default().save_edits(&config).is_err());
+    }
+}
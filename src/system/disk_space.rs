@@ -0,0 +1,51 @@
+// Free space on the filesystem holding the output folder
+
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskSpace {
+    /// Space available to the app (excludes blocks reserved for root)
+    pub free_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Free and total space of the filesystem containing `path`. The path doesn't need to exist
+/// yet; its nearest existing parent is used.
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)] // statvfs field widths differ between 32 and 64-bit targets
+pub fn disk_space(path: &Path) -> Option<DiskSpace> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let absolute = std::path::absolute(path).ok()?;
+    let existing = absolute.ancestors().find(|dir| dir.exists())?;
+    let c_path = std::ffi::CString::new(existing.as_os_str().as_bytes()).ok()?;
+
+    let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: c_path is a valid NUL-terminated string and stats is a writable statvfs
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stats) } != 0 {
+        return None;
+    }
+    let block_size = stats.f_frsize as u64;
+    Some(DiskSpace {
+        free_bytes: stats.f_bavail as u64 * block_size,
+        total_bytes: stats.f_blocks as u64 * block_size,
+    })
+}
+
+#[cfg(not(unix))]
+pub fn disk_space(_path: &Path) -> Option<DiskSpace> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_space_of_missing_folder() {
+        let missing = std::env::temp_dir().join("pixelsort_no_such_dir/nested");
+        let space = disk_space(&missing).unwrap();
+        assert!(space.total_bytes > 0);
+        assert!(space.free_bytes <= space.total_bytes);
+    }
+}
@@ -1,8 +1,12 @@
 // System domain - system-level operations and management
 
+pub mod config;
 pub mod control;
+pub mod disk_space;
 pub mod update_manager;
 
 // Re-export commonly used types
+pub use config::{AppConfig, ConfigWatcher};
 pub use control::SystemControl;
+pub use disk_space::disk_space;
 pub use update_manager::UpdateManager;
@@ -1,21 +1,269 @@
 use crate::PixelSorterApp;
+use crate::ui::state::{BurstState, ShootingMode, SelfTimer};
+use crate::ui::styles::{MenuStyle, button_text};
 use eframe::egui;
+use std::time::Instant;
+
+const BURST_THUMBNAIL_WIDTH: f32 = 280.0;  // Thumbnail width in the burst picker
+const COUNTDOWN_RING_RADIUS: f32 = 160.0;  // Radius of the animated countdown ring
 
 impl PixelSorterApp {
+    /// Shutter button pressed: start the self-timer or fire immediately
+    pub fn trigger_shutter(&mut self, ctx: &egui::Context) {
+        // Shutter stops a running time-lapse
+        if self.timelapse.is_some() {
+            self.stop_timelapse();
+            return;
+        }
+
+        // Shutter finishes a running slit-scan early
+        if self.slit_scan.is_some() {
+            self.finish_slit_scan(ctx);
+            return;
+        }
+
+        if self.countdown_start.is_some() || self.burst.is_some() {
+            return; // Already counting down or shooting
+        }
+
+        if self.self_timer == SelfTimer::Off {
+            self.fire_shutter(ctx);
+        } else {
+            log::info!("Self-timer started ({}s)", self.self_timer.seconds());
+            self.countdown_start = Some(Instant::now());
+        }
+    }
+
+    fn fire_shutter(&mut self, ctx: &egui::Context) {
+        match self.shooting_mode {
+            ShootingMode::Single => self.capture_and_sort(ctx),
+            ShootingMode::Burst => {
+                log::info!("Burst started ({} frames)", self.burst_count);
+                self.burst = Some(BurstState {
+                    frames: Vec::new(),
+                    next_shot: Instant::now(),
+                    sequence: None,
+                });
+            }
+            ShootingMode::Timelapse => self.start_timelapse(),
+            ShootingMode::SlitScan => self.start_slit_scan(),
+        }
+    }
+
+    /// Advance the self-timer and any running burst (called every frame in Input phase)
+    pub fn update_capture_sequence(&mut self, ctx: &egui::Context) {
+        if let Some(start) = self.countdown_start {
+            if start.elapsed().as_secs() >= self.self_timer.seconds() {
+                self.countdown_start = None;
+                self.fire_shutter(ctx);
+            }
+        }
+
+        let shot_due = matches!(&self.burst, Some(burst) if Instant::now() >= burst.next_shot);
+        if shot_due {
+            self.take_burst_frame(ctx);
+        }
+
+        self.update_timelapse();
+    }
+
+    fn take_burst_frame(&mut self, ctx: &egui::Context) {
+        let Some(camera) = self.camera_controller.clone() else {
+            self.burst = None;
+            return;
+        };
+
+        let frame = match camera.try_write() {
+            Ok(mut camera_lock) => camera_lock.capture(self.capture_mode),
+            Err(_) => return, // Camera busy, retry next frame
+        };
+
+        let failed = match frame {
+            Ok(frame) => {
+                let index = self.burst.as_ref().map_or(0, |b| b.frames.len());
+                let sequence = match self.burst.as_ref().and_then(|b| b.sequence) {
+                    Some(sequence) => Ok(sequence),
+                    None => self.next_sequence("burst_"),
+                };
+                if let (Ok(sequence), Some(burst)) = (&sequence, self.burst.as_mut()) {
+                    burst.sequence = Some(*sequence);
+                }
+                if let Err(e) = sequence.and_then(|sequence| self.save_burst_frame(&frame, sequence, index)) {
+                    log::error!("Failed to save burst frame {}: {}", index + 1, e);
+                    self.export_message = Some(format!("✗ Could not save burst frame: {}", e));
+                    self.export_message_time = Some(Instant::now());
+                    self.recheck_disk_usage();
+                }
+                if let Some(burst) = self.burst.as_mut() {
+                    burst.frames.push(frame);
+                    burst.next_shot = Instant::now() + std::time::Duration::from_millis(self.burst_interval_ms);
+                }
+                false
+            }
+            Err(e) => {
+                log::error!("Burst capture failed: {}", e);
+                self.export_message = Some(format!("✗ Burst failed: {}", e));
+                self.export_message_time = Some(Instant::now());
+                true
+            }
+        };
+
+        // Stop on completion or on the first failure, keeping whatever was captured
+        let done = match &self.burst {
+            Some(burst) => burst.frames.len() as u32 >= self.burst_count,
+            None => true,
+        };
+        if done || failed {
+            if let Some(burst) = self.burst.take() {
+                if !burst.frames.is_empty() {
+                    self.finish_burst(ctx, burst.frames);
+                }
+            }
+        }
+    }
+
+    fn finish_burst(&mut self, ctx: &egui::Context, frames: Vec<image::RgbImage>) {
+        log::info!("Burst complete ({} frames)", frames.len());
+
+        self.burst_thumbnails = frames
+            .iter()
+            .enumerate()
+            .map(|(i, frame)| {
+                let thumb_height = (BURST_THUMBNAIL_WIDTH * frame.height() as f32 / frame.width() as f32) as u32;
+                let thumb = image::imageops::thumbnail(frame, BURST_THUMBNAIL_WIDTH as u32, thumb_height.max(1));
+                let size = [thumb.width() as usize, thumb.height() as usize];
+                let color_image = egui::ColorImage::from_rgb(size, thumb.as_raw());
+                ctx.load_texture(format!("burst_thumb_{}", i), color_image, egui::TextureOptions::LINEAR)
+            })
+            .collect();
+        self.burst_frames = frames;
+    }
+
+    /// Send the chosen burst frame to the Edit phase
+    fn select_burst_frame(&mut self, ctx: &egui::Context, index: usize) {
+        let frames = std::mem::take(&mut self.burst_frames);
+        self.burst_thumbnails.clear();
+
+        if let Some(frame) = frames.into_iter().nth(index) {
+            self.original_image = Some(frame.clone());
+            self.processed_image = Some(frame.clone());
+            self.create_processed_texture(ctx, frame);
+            self.preview_mode = false;
+            self.current_phase = crate::ui::Phase::Edit;
+        }
+    }
+
     pub fn capture_and_sort(&mut self, ctx: &egui::Context) {
         if let Some(camera) = self.camera_controller.clone() {
             if let Ok(mut camera_lock) = camera.try_write() {
-                // Stop streaming to free camera for high-quality capture
-                camera_lock.stop_streaming();
-                
-                if let Ok(frame) = camera_lock.capture_snapshot() {
-                    self.original_image = Some(frame.clone());
-                    self.processed_image = Some(frame.clone());
-                    self.create_processed_texture(ctx, frame);
-                    self.preview_mode = false;
-                    self.current_phase = crate::ui::Phase::Edit; // Switch to edit phase
+                match camera_lock.capture(self.capture_mode) {
+                    Ok(frame) => {
+                        self.original_image = Some(frame.clone());
+                        self.processed_image = Some(frame.clone());
+                        self.create_processed_texture(ctx, frame);
+                        self.preview_mode = false;
+                        self.current_phase = crate::ui::Phase::Edit; // Switch to edit phase
+                    }
+                    Err(e) => {
+                        log::error!("Capture failed: {}", e);
+                        self.export_message = Some(format!("✗ Capture failed: {}", e));
+                        self.export_message_time = Some(Instant::now());
+                    }
                 }
             }
         }
     }
-}
\ No newline at end of file
+
+    pub fn render_countdown_overlay(&mut self, ctx: &egui::Context, screen_rect: egui::Rect) {
+        let Some(start) = self.countdown_start else {
+            return;
+        };
+
+        let total = self.self_timer.seconds() as f32;
+        let elapsed = start.elapsed().as_secs_f32();
+        let remaining = (total - elapsed).ceil().max(1.0);
+        // Progress through the current second (0.0 -> 1.0)
+        let second_progress = elapsed.fract();
+
+        let layer_id = egui::LayerId::new(egui::Order::Foreground, egui::Id::new("countdown_overlay"));
+        let painter = ctx.layer_painter(layer_id);
+        let center = screen_rect.center();
+
+        // Dim the preview slightly
+        painter.rect_filled(screen_rect, 0.0, egui::Color32::from_black_alpha(80));
+
+        // Ring that drains once per second
+        painter.circle_stroke(center, COUNTDOWN_RING_RADIUS, egui::Stroke::new(10.0, egui::Color32::from_white_alpha(40)));
+        let num_points = 64;
+        let sweep = std::f32::consts::TAU * (1.0 - second_progress);
+        let start_angle = -std::f32::consts::FRAC_PI_2;
+        let points: Vec<egui::Pos2> = (0..=num_points)
+            .map(|i| {
+                let angle = start_angle + sweep * i as f32 / num_points as f32;
+                center + egui::vec2(angle.cos(), angle.sin()) * COUNTDOWN_RING_RADIUS
+            })
+            .collect();
+        painter.add(egui::Shape::line(points, egui::Stroke::new(10.0, egui::Color32::WHITE)));
+
+        // Number pops in large at the start of each second and settles
+        let pop = 1.0 + (1.0 - second_progress).powi(3) * 0.4;
+        let galley = painter.layout_no_wrap(
+            format!("{:.0}", remaining),
+            egui::FontId::proportional(160.0 * pop),
+            egui::Color32::WHITE,
+        );
+        painter.galley(center - galley.size() / 2.0, galley);
+
+        ctx.request_repaint();
+    }
+
+    pub fn render_burst_picker(&mut self, ctx: &egui::Context) {
+        if self.burst_thumbnails.is_empty() {
+            return;
+        }
+
+        let style = MenuStyle::usb_export();
+        let mut selected = None;
+        let mut cancelled = false;
+
+        let response = egui::Window::new("📸 Pick a frame")
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal_wrapped(|ui| {
+                    for (i, thumb) in self.burst_thumbnails.iter().enumerate() {
+                        let size = thumb.size_vec2() * (BURST_THUMBNAIL_WIDTH / thumb.size_vec2().x);
+                        if ui.add(egui::ImageButton::new((thumb.id(), size))).clicked() {
+                            selected = Some(i);
+                        }
+                    }
+                });
+
+                ui.add_space(style.spacing);
+
+                ui.vertical_centered(|ui| {
+                    if ui.add_sized(
+                        [style.button_width, style.cancel_button_height],
+                        egui::Button::new(button_text("Discard burst", style.label_size)))
+                        .clicked()
+                    {
+                        cancelled = true;
+                    }
+                });
+            });
+
+        // Force window to top layer
+        if let Some(response) = response {
+            ctx.move_to_top(response.response.layer_id);
+        }
+
+        if let Some(index) = selected {
+            log::info!("Burst frame {} selected for editing", index + 1);
+            self.select_burst_frame(ctx, index);
+        } else if cancelled {
+            self.burst_frames.clear();
+            self.burst_thumbnails.clear();
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use crate::PixelSorterApp;
+use crate::system::{AppConfig, UpdateManager};
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+impl PixelSorterApp {
+    /// Reload pixelsort.toml when it changes on disk. An invalid file keeps the current settings.
+    pub fn poll_config_reload(&mut self, ctx: &egui::Context) {
+        self.apply_pending_camera_config();
+
+        if self.last_config_check.is_some_and(|t| t.elapsed() < CONFIG_CHECK_INTERVAL) {
+            return;
+        }
+        self.last_config_check = Some(Instant::now());
+
+        if !self.config_watcher.changed() {
+            return;
+        }
+
+        let message = match AppConfig::load() {
+            Ok(config) => {
+                log::info!("Configuration changed - applying");
+                self.config_error = None;
+                self.apply_config(ctx, config);
+                "✓ Settings reloaded".to_string()
+            }
+            Err(e) => {
+                log::error!("Ignoring invalid configuration: {}", e);
+                let message = format!("✗ Config error: {} (keeping previous settings)", e);
+                self.config_error = Some(e);
+                message
+            }
+        };
+        self.export_message = Some(message);
+        self.export_message_time = Some(Instant::now());
+    }
+
+    /// Switch to a new configuration, updating the parts that were read at startup
+    pub fn apply_config(&mut self, ctx: &egui::Context, config: AppConfig) {
+        let old = std::mem::replace(&mut self.config, config);
+
+        // Display
+        if (old.display.width, old.display.height) != (self.config.display.width, self.config.display.height) {
+            let size = egui::vec2(self.config.display.width as f32, self.config.display.height as f32);
+            ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(size));
+        }
+        if old.display.fullscreen != self.config.display.fullscreen {
+            ctx.send_viewport_cmd(egui::ViewportCommand::Fullscreen(self.config.display.fullscreen));
+        }
+
+        // Storage: the next save starts a session folder in the new location
+        if old.paths.output_dir != self.config.paths.output_dir {
+            log::info!("Output directory changed to {}", self.config.paths.output_dir.display());
+            self.current_session_folder = None;
+        }
+
+        if old.export.delete_after_copy != self.config.export.delete_after_copy && !self.show_usb_export_dialog {
+            self.usb_export_delete_after = self.config.export.delete_after_copy;
+        }
+        let export_defaults_changed = old.export.mode != self.config.export.mode
+            || old.export.zip_per_session != self.config.export.zip_per_session
+            || old.paths.output_dir != self.config.paths.output_dir;
+        if export_defaults_changed && !self.show_usb_export_dialog {
+            self.reset_export_selection();
+        }
+
+        // New thresholds or limits take effect straight away
+        if old.storage != self.config.storage {
+            self.recheck_disk_usage();
+        }
+
+        if old.update.install_dir != self.config.update.install_dir {
+            self.update_manager = UpdateManager::new(self.config.update.install_dir.clone());
+        }
+
+        // Camera (retried on later frames if a capture is holding the lock)
+        self.pending_camera_config = Some(self.config.camera_config());
+        self.apply_pending_camera_config();
+
+        crate::hardware::update_monitoring(self.config.ups_config());
+
+        // Sleep timeout and splash length are read from self.config every frame
+    }
+
+    /// Hand queued camera settings to the camera once no capture holds it
+    fn apply_pending_camera_config(&mut self) {
+        let Some(camera) = &self.camera_controller else {
+            self.pending_camera_config = None;
+            return;
+        };
+        let Some(config) = &self.pending_camera_config else {
+            return;
+        };
+
+        match camera.try_write() {
+            Ok(mut camera) => {
+                camera.apply_config(config);
+                self.pending_camera_config = None;
+            }
+            Err(_) => log::debug!("Camera busy - camera settings will be applied after the capture"),
+        }
+    }
+}
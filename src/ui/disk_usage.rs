@@ -0,0 +1,54 @@
+use crate::PixelSorterApp;
+use crate::session::retention;
+use crate::ui::indicators::StatusBanner;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const DISK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+impl PixelSorterApp {
+    /// Apply the retention rules and check free space once a minute
+    pub fn poll_disk_usage(&mut self) {
+        if self.last_disk_check.is_some_and(|t| t.elapsed() < DISK_CHECK_INTERVAL) {
+            return;
+        }
+        self.last_disk_check = Some(Instant::now());
+
+        // Never prune while an export is reading the sessions
+        let rules = self.config.retention_rules();
+        if rules.is_enabled() && self.usb_export_job.is_none() {
+            let output_dir = &self.config.paths.output_dir;
+            let usage = retention::session_usage(output_dir);
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs());
+            let to_prune = retention::sessions_to_prune(&usage, &rules, now, self.current_session_folder.as_deref());
+            if !to_prune.is_empty() {
+                let (removed, freed) = retention::prune_sessions(output_dir, &usage, &to_prune);
+                if removed > 0 {
+                    self.export_message = Some(format!("✓ Removed {} old sessions, freed {:.0} MB", removed, freed as f64 / 1e6));
+                    self.export_message_time = Some(Instant::now());
+                }
+            }
+        }
+
+        let Some(space) = crate::system::disk_space(&self.config.paths.output_dir) else {
+            return;
+        };
+        let low = space.free_bytes < self.config.storage.low_space_mb * 1024 * 1024;
+        if low && self.low_disk_space.is_none() {
+            log::warn!("Low disk space: {} MB free", space.free_bytes / (1024 * 1024));
+            self.export_message = Some(format!("⚠ Storage almost full: {} MB free", space.free_bytes / (1024 * 1024)));
+            self.export_message_time = Some(Instant::now());
+        }
+        self.low_disk_space = low.then_some(space.free_bytes);
+    }
+
+    /// Check again on the next frame, e.g. after a failed save
+    pub fn recheck_disk_usage(&mut self) {
+        self.last_disk_check = None;
+    }
+
+    /// Persistent warning at the top of the screen while space is low
+    pub fn disk_warning_banner(&self) -> Option<StatusBanner> {
+        let free_bytes = self.low_disk_space?;
+        Some(StatusBanner::warning(format!("⚠ Storage almost full: {} MB free", free_bytes / (1024 * 1024))))
+    }
+}
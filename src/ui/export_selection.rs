@@ -0,0 +1,180 @@
+use crate::PixelSorterApp;
+use crate::session::library::{self, ExportMode, Session};
+use eframe::egui;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use super::styles::{MenuStyle, button_text};
+
+const ROW_HEIGHT: f32 = 48.0;
+const STAR_BUTTON_WIDTH: f32 = 56.0;
+
+impl PixelSorterApp {
+    /// Sessions and iterations picked for export, in the USB export dialog
+    pub fn render_export_selection(&mut self, ui: &mut egui::Ui, style: &MenuStyle) {
+        let sessions = self
+            .usb_export_sessions
+            .get_or_insert_with(|| library::scan_sessions(&self.config.paths.output_dir));
+        if self.usb_export_selected.is_none() {
+            self.usb_export_selected = Some(library::files_for_mode(sessions.iter(), self.usb_export_mode));
+        }
+        let selected = self.usb_export_selected.get_or_insert_with(Default::default);
+
+        // Export mode, which resets the selection
+        ui.horizontal(|ui| {
+            for mode in ExportMode::ALL {
+                let button = egui::SelectableLabel::new(self.usb_export_mode == mode, button_text(mode.label(), style.label_size * 0.8));
+                if ui.add_sized([(style.button_width - 16.0) / 3.0, ROW_HEIGHT], button).clicked() {
+                    self.usb_export_mode = mode;
+                    *selected = library::files_for_mode(sessions.iter(), mode);
+                }
+            }
+        });
+
+        let mut favourite_toggled = None;
+        egui::ScrollArea::vertical()
+            .max_height(ui.ctx().screen_rect().height() * 0.35)
+            .show(ui, |ui| {
+                ui.set_width(style.button_width);
+                if sessions.is_empty() {
+                    ui.label(button_text("No saved sessions yet", style.label_size * 0.9).color(egui::Color32::GRAY));
+                }
+
+                for session in sessions.iter() {
+                    let session_files = session.files_for_mode(self.usb_export_mode);
+                    let chosen = session_files.iter().filter(|file| selected.contains(*file)).count();
+
+                    ui.horizontal(|ui| {
+                        let mut checked = chosen > 0;
+                        let text = format!("{} ({}/{})", session_title(&session.name), chosen, session_files.len());
+                        let checkbox = egui::Checkbox::new(&mut checked, button_text(&text, style.label_size * 0.9));
+                        if ui.add_enabled(!session_files.is_empty(), checkbox).changed() {
+                            for file in session_files {
+                                if checked {
+                                    selected.insert(file);
+                                } else {
+                                    selected.remove(&file);
+                                }
+                            }
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            let expanded = self.usb_export_expanded.as_deref() == Some(session.name.as_str());
+                            let arrow = if expanded { "⏶" } else { "⏷" };
+                            if ui.add_sized([STAR_BUTTON_WIDTH, ROW_HEIGHT], egui::Button::new(button_text(arrow, style.label_size))).clicked() {
+                                self.usb_export_expanded = if expanded { None } else { Some(session.name.clone()) };
+                            }
+                        });
+                    });
+
+                    if self.usb_export_expanded.as_deref() == Some(session.name.as_str()) {
+                        ui.indent(&session.name, |ui| {
+                            for file_name in &session.other_files {
+                                file_row(ui, style, selected, session, file_name, None);
+                            }
+                            for iteration in &session.iterations {
+                                if file_row(ui, style, selected, session, &iteration.file_name, Some(iteration.favourite)) {
+                                    favourite_toggled = Some((session.name.clone(), iteration.file_name.clone(), !iteration.favourite));
+                                }
+                                if let Some(share_file) = &iteration.share_file {
+                                    file_row(ui, style, selected, session, share_file, None);
+                                }
+                            }
+                        });
+                    }
+                }
+            });
+
+        if let Some((session_name, file_name, favourite)) = favourite_toggled {
+            self.toggle_favourite(&session_name, &file_name, favourite);
+        }
+
+        let count = self.usb_export_selected.as_ref().map_or(0, |selected| selected.len());
+        ui.label(button_text(&format!("{} files selected", count), style.label_size * 0.9));
+    }
+
+    /// Star or unstar an iteration on disk and in the dialog
+    fn toggle_favourite(&mut self, session_name: &str, file_name: &str, favourite: bool) {
+        if let Err(e) = library::set_favourite(&self.config.paths.output_dir, session_name, file_name, favourite) {
+            log::error!("Could not update favourites in {}: {}", session_name, e);
+            self.export_message = Some(format!("✗ Could not save favourite: {}", e));
+            self.export_message_time = Some(Instant::now());
+            return;
+        }
+
+        let session = self
+            .usb_export_sessions
+            .iter_mut()
+            .flatten()
+            .find(|session| session.name == session_name);
+        if let Some(iteration) = session
+            .and_then(|session| session.iterations.iter_mut().find(|iteration| iteration.file_name == file_name))
+        {
+            iteration.favourite = favourite;
+        }
+
+        // In favourites mode the star is the selection
+        if self.usb_export_mode == ExportMode::FavouritesOnly {
+            if let Some(selected) = &mut self.usb_export_selected {
+                let path = Path::new(session_name).join(file_name);
+                if favourite {
+                    selected.insert(path);
+                } else {
+                    selected.remove(&path);
+                }
+            }
+        }
+    }
+
+    /// Forget the dialog's selection so it is rebuilt from disk next time
+    pub fn reset_export_selection(&mut self) {
+        self.usb_export_sessions = None;
+        self.usb_export_selected = None;
+        self.usb_export_expanded = None;
+        self.usb_export_mode = self.config.export_mode();
+        self.usb_export_zip = self.config.export.zip_per_session;
+    }
+}
+
+/// Checkbox for one file, with a star button for iterations. Returns true if the star was tapped.
+fn file_row(
+    ui: &mut egui::Ui,
+    style: &MenuStyle,
+    selected: &mut std::collections::BTreeSet<PathBuf>,
+    session: &Session,
+    file_name: &str,
+    favourite: Option<bool>,
+) -> bool {
+    let path = Path::new(&session.name).join(file_name);
+    let mut star_tapped = false;
+    ui.horizontal(|ui| {
+        if let Some(favourite) = favourite {
+            let (star, color) = if favourite { ("★", egui::Color32::GOLD) } else { ("☆", egui::Color32::GRAY) };
+            star_tapped = ui
+                .add_sized([STAR_BUTTON_WIDTH, ROW_HEIGHT], egui::Button::new(button_text(star, style.label_size).color(color)))
+                .clicked();
+        } else {
+            ui.add_space(STAR_BUTTON_WIDTH + ui.spacing().item_spacing.x);
+        }
+
+        let mut checked = selected.contains(&path);
+        if ui.checkbox(&mut checked, button_text(file_name, style.label_size * 0.75)).changed() {
+            if checked {
+                selected.insert(path.clone());
+            } else {
+                selected.remove(&path);
+            }
+        }
+    });
+    star_tapped
+}
+
+/// "session_20240101_120000" → "2024-01-01 12:00"
+fn session_title(name: &str) -> String {
+    let stamp = name.trim_start_matches("session_");
+    match (stamp.get(0..4), stamp.get(4..6), stamp.get(6..8), stamp.get(9..11), stamp.get(11..13)) {
+        (Some(year), Some(month), Some(day), Some(hour), Some(minute)) => {
+            format!("{}-{}-{} {}:{}", year, month, day, hour, minute)
+        }
+        _ => name.to_string(),
+    }
+}
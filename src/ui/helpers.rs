@@ -17,3 +17,9 @@ pub fn center_rect_in_rect(content_size: egui::Vec2, container: egui::Rect) -> e
     let offset = (container.size() - content_size) * 0.5;
     egui::Rect::from_min_size(container.min + offset, content_size)
 }
+
+/// The option after `current`, wrapping around; the first one if `current` isn't listed
+pub fn next_option<T: PartialEq + Copy>(options: &[T], current: T) -> T {
+    let idx = options.iter().position(|option| *option == current).map_or(0, |idx| idx + 1);
+    options[idx % options.len()]
+}
@@ -0,0 +1,426 @@
+use crate::PixelSorterApp;
+use crate::hardware::RemovableDrive;
+use crate::session::import::{self, ImageFile, SortOrder};
+use crate::ui::state::{BrowserSource, BrowserState};
+use eframe::egui;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Instant;
+use super::styles::{MenuStyle, button_green, button_text};
+
+const THUMBNAIL_SIZE: f32 = 180.0;
+/// Thumbnails uploaded to the GPU per frame, so a full folder doesn't stall the UI
+const THUMBNAILS_PER_FRAME: usize = 4;
+const NAV_BUTTON_HEIGHT: f32 = 56.0;
+
+impl PixelSorterApp {
+    /// Open the browser to import from a drive; its images are searched in the background
+    pub fn open_usb_import(&mut self, drive: &RemovableDrive) {
+        let (sender, receiver) = mpsc::channel();
+        let mount_point = drive.mount_point.clone();
+        std::thread::spawn(move || {
+            let images = import::find_images(&mount_point);
+            log::info!("Import: {} images found on {}", images.len(), mount_point.display());
+            let _ = sender.send(images);
+        });
+
+        let source = BrowserSource::Drive { mount_point: drive.mount_point.clone(), label: drive.label.clone() };
+        let mut browser = new_browser(source, Vec::new(), Vec::new());
+        browser.search_result = Some(receiver);
+        self.image_browser = Some(browser);
+        self.show_usb_export_dialog = false;
+    }
+
+    /// Browse the output folder and configured image folders (replaces the desktop file dialog)
+    pub fn open_image_browser(&mut self) {
+        let mut roots = vec![self.config.paths.output_dir.clone()];
+        roots.extend(self.config.paths.image_folders.iter().cloned());
+        roots.retain(|root| root.is_dir());
+        roots.dedup();
+
+        let mut browser = new_browser(BrowserSource::Local, roots, Vec::new());
+        if let [root] = browser.roots.as_slice() {
+            let root = root.clone();
+            show_folder(&mut browser, Some(root));
+        }
+        self.image_browser = Some(browser);
+    }
+
+    /// Touch browser with large thumbnails and multi-select
+    pub fn render_image_browser(&mut self, ctx: &egui::Context) {
+        let Some(browser) = &mut self.image_browser else {
+            return;
+        };
+
+        // Close if the drive was pulled (unless the copy already finished reading it)
+        if let BrowserSource::Drive { mount_point, label } = &browser.source {
+            let attached = crate::hardware::removable_drives().iter().any(|drive| &drive.mount_point == mount_point);
+            if !attached && browser.copy_result.is_none() {
+                self.export_message = Some(format!("✗ {} was removed", label));
+                self.export_message_time = Some(Instant::now());
+                self.image_browser = None;
+                return;
+            }
+        }
+
+        // Drive search finished: show the images and start on their thumbnails
+        let search_done = browser.search_result.as_ref().and_then(|receiver| match receiver.try_recv() {
+            Ok(images) => Some(images),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Vec::new()),
+        });
+        if let Some(mut images) = search_done {
+            browser.sort.sort(&mut images);
+            browser.thumbnail_receiver = load_thumbnails(images.iter().map(|image| image.path.clone()).collect());
+            browser.images = images;
+            browser.search_result = None;
+        }
+
+        for (path, thumbnail) in browser.thumbnail_receiver.try_iter().take(THUMBNAILS_PER_FRAME) {
+            let texture = thumbnail.map(|thumbnail| {
+                ctx.load_texture(format!("browser_thumb_{}", path.display()), thumbnail, egui::TextureOptions::LINEAR)
+            });
+            browser.thumbnails.insert(path, texture);
+        }
+        let thumbnails_pending = browser.images.iter().any(|image| !browser.thumbnails.contains_key(&image.path));
+        if thumbnails_pending || browser.copy_result.is_some() || browser.search_result.is_some() {
+            ctx.request_repaint_after(std::time::Duration::from_millis(50));
+        }
+
+        if browser.copy_result.is_some() {
+            self.poll_image_import(ctx);
+        }
+        let Some(browser) = &mut self.image_browser else {
+            return;
+        };
+
+        let style = MenuStyle::image_browser();
+        let mut open = false;
+        let mut close = false;
+        let mut navigate_to = None;
+
+        let title = match &browser.source {
+            BrowserSource::Drive { label, .. } => format!("📥 Import from {}", label),
+            BrowserSource::Local => "📂 Open images".to_string(),
+        };
+        let response = egui::Window::new(title)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.set_min_width(style.width);
+
+                if browser.copy_result.is_some() {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(style.spacing);
+                        ui.spinner();
+                        ui.label(button_text(&format!("Copying {} images...", browser.selected.len()), style.label_size));
+                        ui.add_space(style.spacing);
+                    });
+                    return;
+                }
+
+                if browser.search_result.is_some() {
+                    ui.vertical_centered(|ui| {
+                        ui.add_space(style.spacing);
+                        ui.spinner();
+                        ui.label(button_text("Looking for images...", style.label_size));
+                        ui.add_space(style.spacing);
+                    });
+                    if ui.add_sized([style.width, style.cancel_button_height], egui::Button::new(button_text("Cancel", style.label_size))).clicked() {
+                        close = true;
+                    }
+                    return;
+                }
+
+                // Location and sort order
+                ui.horizontal(|ui| {
+                    if let Some(current) = &browser.current_dir {
+                        if ui.add_sized([120.0, NAV_BUTTON_HEIGHT], egui::Button::new(button_text("⬆ Up", style.label_size))).clicked() {
+                            navigate_to = Some(parent_folder(browser, current));
+                        }
+                        ui.label(button_text(&current.display().to_string(), style.label_size * 0.8));
+                    }
+                });
+                ui.horizontal(|ui| {
+                    for order in SortOrder::ALL {
+                        let button = egui::SelectableLabel::new(browser.sort == order, button_text(order.label(), style.label_size * 0.9));
+                        if ui.add_sized([(style.width - 3.0 * style.spacing) / 4.0, NAV_BUTTON_HEIGHT], button).clicked() {
+                            browser.sort = order;
+                            order.sort(&mut browser.images);
+                        }
+                    }
+                });
+
+                ui.add_space(style.spacing * 0.5);
+
+                egui::ScrollArea::vertical()
+                    .max_height(ctx.screen_rect().height() * 0.55)
+                    .show(ui, |ui| {
+                        ui.set_width(style.width);
+
+                        let folders: Vec<PathBuf> = match browser.current_dir {
+                            None => browser.roots.clone(),
+                            Some(_) => browser.folders.clone(),
+                        };
+                        let folders_empty = folders.is_empty();
+                        for folder in folders {
+                            let name = match browser.current_dir {
+                                None => folder.display().to_string(),
+                                Some(_) => folder.file_name().map_or_else(|| folder.display().to_string(), |name| name.to_string_lossy().to_string()),
+                            };
+                            if ui.add_sized([style.width, NAV_BUTTON_HEIGHT], egui::Button::new(button_text(&format!("📁 {}", name), style.label_size))).clicked() {
+                                navigate_to = Some(Some(folder));
+                            }
+                        }
+
+                        if browser.images.is_empty() && folders_empty {
+                            ui.label(button_text("No images here", style.label_size).color(egui::Color32::GRAY));
+                        }
+
+                        ui.horizontal_wrapped(|ui| {
+                            for image in &browser.images {
+                                if thumbnail_button(ui, &style, image, browser.thumbnails.get(&image.path).and_then(Option::as_ref), browser.selected.contains(&image.path)) {
+                                    toggle_selected(&mut browser.selected, &image.path);
+                                }
+                            }
+                        });
+                    });
+
+                ui.add_space(style.spacing);
+
+                ui.horizontal(|ui| {
+                    let half = [(style.width - style.spacing) / 2.0, style.cancel_button_height];
+                    if ui.add_sized(half, egui::Button::new(button_text("Select all", style.label_size))).clicked() {
+                        for image in &browser.images {
+                            if !browser.selected.contains(&image.path) {
+                                browser.selected.push(image.path.clone());
+                            }
+                        }
+                    }
+                    if ui.add_sized(half, egui::Button::new(button_text("Select none", style.label_size))).clicked() {
+                        browser.selected.clear();
+                    }
+                });
+
+                ui.add_space(style.spacing);
+
+                ui.vertical_centered(|ui| {
+                    let count = browser.selected.len();
+                    let label = match (&browser.source, count) {
+                        (BrowserSource::Drive { .. }, _) => format!("✓ Import {} images", count),
+                        (BrowserSource::Local, 1) => "✓ Open".to_string(),
+                        (BrowserSource::Local, _) => format!("✓ Open {} images in a new session", count),
+                    };
+                    let open_button = egui::Button::new(button_text(&label, style.label_size))
+                        .fill(button_green())
+                        .min_size(egui::vec2(style.button_width, style.button_height));
+                    if ui.add_enabled(count > 0, open_button).clicked() {
+                        open = true;
+                    }
+
+                    ui.add_space(style.spacing * 0.5);
+
+                    if ui.add_sized(
+                        [style.button_width, style.cancel_button_height],
+                        egui::Button::new(button_text("Cancel", style.label_size)))
+                        .clicked()
+                    {
+                        close = true;
+                    }
+                });
+            });
+
+        // Force window to top layer
+        if let Some(response) = response {
+            ctx.move_to_top(response.response.layer_id);
+        }
+
+        if let Some(folder) = navigate_to {
+            show_folder(browser, folder);
+        }
+        if open {
+            self.open_selected_images(ctx);
+        } else if close {
+            self.image_browser = None;
+        }
+    }
+
+    /// Open one local image directly; anything else is copied into a new session first
+    fn open_selected_images(&mut self, ctx: &egui::Context) {
+        let Some(browser) = &self.image_browser else {
+            return;
+        };
+        if browser.source == BrowserSource::Local && browser.selected.len() == 1 {
+            let path = browser.selected[0].clone();
+            self.image_browser = None;
+            self.open_in_editor(ctx, &path);
+            return;
+        }
+
+        // Imports always start a fresh session
+        self.current_session_folder = None;
+        let session_dir = match self.ensure_session_dir() {
+            Ok(dir) => dir,
+            Err(e) => {
+                log::error!("Cannot create session for import: {}", e);
+                self.export_message = Some(format!("✗ Import failed: {}", e));
+                self.export_message_time = Some(Instant::now());
+                return;
+            }
+        };
+        let Some(browser) = &mut self.image_browser else {
+            return;
+        };
+
+        let files = browser.selected.clone();
+        log::info!("Importing {} images into {}", files.len(), session_dir.display());
+
+        let (sender, receiver) = mpsc::channel();
+        browser.copy_result = Some(receiver);
+        std::thread::spawn(move || {
+            let result = import::import_images(&files, &session_dir).map_err(|e| e.to_string());
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Open the first imported image in Edit once the copy finishes
+    fn poll_image_import(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = self.image_browser.as_ref().and_then(|browser| browser.copy_result.as_ref()) else {
+            return;
+        };
+        let result = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err("Import thread stopped".to_string()),
+        };
+        self.image_browser = None;
+
+        match result {
+            Ok(imported) => {
+                log::info!("Imported {} images", imported.len());
+                self.export_message = Some(format!("✓ Imported {} images", imported.len()));
+                self.export_message_time = Some(Instant::now());
+                if let Some(first) = imported.first() {
+                    self.iteration_counter = 0;
+                    self.open_in_editor(ctx, first);
+                }
+            }
+            Err(e) => {
+                log::error!("Import failed: {}", e);
+                self.export_message = Some(format!("✗ Import failed: {}", e));
+                self.export_message_time = Some(Instant::now());
+            }
+        }
+    }
+
+    fn open_in_editor(&mut self, ctx: &egui::Context, path: &Path) {
+        match image::open(path) {
+            Ok(img) => {
+                let rgb_image = img.to_rgb8();
+                self.original_image = Some(rgb_image.clone());
+                self.processed_image = Some(rgb_image.clone());
+                self.create_processed_texture(ctx, rgb_image);
+                self.preview_mode = false;
+                self.current_phase = crate::ui::Phase::Edit;
+            }
+            Err(e) => {
+                log::error!("Cannot open {}: {}", path.display(), e);
+                self.export_message = Some(format!("✗ Could not open {}", path.display()));
+                self.export_message_time = Some(Instant::now());
+            }
+        }
+    }
+}
+
+fn new_browser(source: BrowserSource, roots: Vec<PathBuf>, images: Vec<ImageFile>) -> BrowserState {
+    let thumbnail_receiver = load_thumbnails(images.iter().map(|image| image.path.clone()).collect());
+    BrowserState {
+        source,
+        roots,
+        current_dir: None,
+        folders: Vec::new(),
+        images,
+        sort: SortOrder::NewestFirst,
+        selected: Vec::new(),
+        thumbnails: HashMap::new(),
+        search_result: None,
+        thumbnail_receiver,
+        copy_result: None,
+    }
+}
+
+/// Switch to a folder (None: back to the list of roots); the selection is kept
+fn show_folder(browser: &mut BrowserState, folder: Option<PathBuf>) {
+    let listing = folder.as_deref().map(import::list_folder).unwrap_or_default();
+    browser.folders = listing.folders;
+    browser.images = listing.images;
+    browser.sort.sort(&mut browser.images);
+    browser.current_dir = folder;
+
+    // Replacing the receiver stops the previous folder's loader
+    let missing = browser
+        .images
+        .iter()
+        .map(|image| image.path.clone())
+        .filter(|path| !browser.thumbnails.contains_key(path))
+        .collect();
+    browser.thumbnail_receiver = load_thumbnails(missing);
+}
+
+/// Where "Up" goes: the parent folder, or the root list when leaving a root
+fn parent_folder(browser: &BrowserState, current: &Path) -> Option<PathBuf> {
+    if browser.roots.iter().any(|root| root == current) {
+        return None;
+    }
+    current.parent().map(Path::to_path_buf)
+}
+
+/// Decode thumbnails off the UI thread; the loader stops once its receiver is dropped
+fn load_thumbnails(paths: Vec<PathBuf>) -> mpsc::Receiver<(PathBuf, Option<egui::ColorImage>)> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for path in paths {
+            let thumbnail = match image::open(&path) {
+                Ok(image) => {
+                    let thumb = image.thumbnail(THUMBNAIL_SIZE as u32, THUMBNAIL_SIZE as u32).to_rgb8();
+                    let size = [thumb.width() as usize, thumb.height() as usize];
+                    Some(egui::ColorImage::from_rgb(size, thumb.as_raw()))
+                }
+                Err(e) => {
+                    log::debug!("No thumbnail for {}: {}", path.display(), e);
+                    None
+                }
+            };
+            if sender.send((path, thumbnail)).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Thumbnail (or the file name while it decodes, or if it can't be); returns true when tapped
+fn thumbnail_button(ui: &mut egui::Ui, style: &MenuStyle, image: &ImageFile, texture: Option<&egui::TextureHandle>, selected: bool) -> bool {
+    let response = match texture {
+        Some(texture) => {
+            let size = texture.size_vec2() * (THUMBNAIL_SIZE / texture.size_vec2().max_elem());
+            ui.add_sized([THUMBNAIL_SIZE, THUMBNAIL_SIZE], egui::ImageButton::new((texture.id(), size)).selected(selected))
+        }
+        None => ui.add_sized(
+            [THUMBNAIL_SIZE, THUMBNAIL_SIZE],
+            egui::SelectableLabel::new(selected, button_text(&image.display_name, style.label_size * 0.6)),
+        ),
+    };
+    response.on_hover_text(&image.display_name).clicked()
+}
+
+fn toggle_selected(selected: &mut Vec<PathBuf>, path: &Path) {
+    match selected.iter().position(|chosen| chosen == path) {
+        Some(index) => {
+            selected.remove(index);
+        }
+        None => selected.push(path.to_path_buf()),
+    }
+}
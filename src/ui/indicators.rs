@@ -1,9 +1,63 @@
 use crate::PixelSorterApp;
 use eframe::egui;
+use std::time::Instant;
 
 const UI_PADDING: f32 = 20.0;
+const BANNER_SPACING: f32 = 12.0;  // Gap between stacked status banners
+
+/// One line in the stack of status banners at the top of the screen
+pub struct StatusBanner {
+    pub text: String,
+    pub color: egui::Color32,
+    pub size: f32,
+}
+
+impl StatusBanner {
+    /// Progress of a running capture
+    pub fn info(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: egui::Color32::WHITE, size: 28.0 }
+    }
+
+    /// Something the user should act on soon
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self { text: text.into(), color: egui::Color32::from_rgb(220, 180, 50), size: 24.0 }
+    }
+}
+
+/// Translucent dark panel behind indicators drawn over the camera preview
+pub fn overlay_frame() -> egui::Frame {
+    egui::Frame::none()
+        .fill(egui::Color32::from_rgba_unmultiplied(0, 0, 0, 180))
+        .rounding(egui::Rounding::same(16.0))
+        .inner_margin(egui::Margin::symmetric(24.0, 16.0))
+}
 
 impl PixelSorterApp {
+    /// Capture progress and warnings, stacked top-centre so they never overlap
+    pub fn render_status_banners(&mut self, ctx: &egui::Context) {
+        let banners: Vec<StatusBanner> = [self.timelapse_banner(), self.slit_scan_banner(), self.disk_warning_banner()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if banners.is_empty() {
+            return;
+        }
+
+        egui::Area::new("status_banners")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, UI_PADDING))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                ui.spacing_mut().item_spacing.y = BANNER_SPACING;
+                ui.vertical_centered(|ui| {
+                    for banner in banners {
+                        overlay_frame().show(ui, |ui| {
+                            ui.label(egui::RichText::new(banner.text).color(banner.color).size(banner.size));
+                        });
+                    }
+                });
+            });
+    }
+
     pub fn render_export_message(&mut self, ctx: &egui::Context, _screen_rect: egui::Rect) {
         // Auto-hide message after 3 seconds
         if let Some(message_time) = self.export_message_time {
@@ -39,6 +93,18 @@ impl PixelSorterApp {
         }
     }
 
+    /// Toast when external power is lost or comes back
+    pub fn poll_power_events(&mut self) {
+        for event in crate::hardware::take_power_events() {
+            let message = match event {
+                crate::hardware::PowerEvent::Lost => "⚠ Power lost - running on battery",
+                crate::hardware::PowerEvent::Restored => "✓ Power restored",
+            };
+            self.export_message = Some(message.to_string());
+            self.export_message_time = Some(Instant::now());
+        }
+    }
+
     pub fn render_battery_indicator(&mut self, ctx: &egui::Context, _screen_rect: egui::Rect) {
         let battery_status = crate::hardware::get_battery_status();
 
@@ -51,10 +117,7 @@ impl PixelSorterApp {
             .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-UI_PADDING, UI_PADDING))
             .order(egui::Order::Foreground)
             .show(ctx, |ui| {
-                egui::Frame::none()
-                    .fill(egui::Color32::from_rgba_unmultiplied(0, 0, 0, 180))
-                    .rounding(egui::Rounding::same(16.0)) // Doubled from 8.0
-                    .inner_margin(egui::Margin::symmetric(24.0, 16.0)) // Doubled from (12.0, 8.0)
+                overlay_frame()
                     .show(ui, |ui| {
                         ui.horizontal(|ui| {
                             // Battery icon (simple rectangle representation)
@@ -115,6 +178,24 @@ impl PixelSorterApp {
                                     .color(egui::Color32::from_rgb(180, 180, 180))
                                     .size(24.0) // Doubled from 12.0
                             );
+
+                            // Mains/USB-C supply is gone
+                            if battery_status.power_source == crate::hardware::PowerSource::Battery {
+                                ui.label(
+                                    egui::RichText::new("On battery")
+                                        .color(egui::Color32::from_rgb(220, 180, 50))
+                                        .size(24.0)
+                                );
+                            }
+
+                            // Remaining runtime (or time to full while charging)
+                            if let Some(estimate) = battery_status.runtime_estimate {
+                                ui.label(
+                                    egui::RichText::new(crate::hardware::format_runtime(estimate))
+                                        .color(egui::Color32::from_rgb(180, 180, 180))
+                                        .size(24.0)
+                                );
+                            }
                         });
                     });
             });
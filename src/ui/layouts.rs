@@ -3,7 +3,8 @@
 /// EDIT THIS FILE TO CHANGE: Positions, padding, spacing, alignment
 /// (Colors/appearance are in styles.rs)
 use crate::PixelSorterApp;
-use crate::ui::state::Phase;
+use crate::ui::state::{Phase, ShootingMode, BURST_COUNTS, BURST_INTERVALS_MS, TIMELAPSE_DURATIONS, TIMELAPSE_INTERVALS};
+use crate::ui::helpers::next_option;
 use crate::ui::components::{circular_button, circular_button_default, circular_button_light, vertical_slider, slider_knob_radius};
 use crate::ui::styles::{ButtonSizes, SliderSizes, button_dark, button_green, button_red, button_fill_normal};
 use crate::processing::SortingAlgorithm;
@@ -68,13 +69,30 @@ impl PixelSorterApp {
             large_center.y - sizes.large_radius - sizes.small_radius - sizes.spacing,
         );
 
+        // Further small buttons stack upwards above the Upload button
+        let small_step = sizes.small_radius * 2.0 + sizes.spacing;
+        let mode_center = small_center - egui::vec2(0.0, small_step);
+        let timer_center = mode_center - egui::vec2(0.0, small_step);
+        let shooting_center = timer_center - egui::vec2(0.0, small_step);
+
+        // Show burst progress on the shutter while shooting
+        let shutter_label = if let Some(burst) = &self.burst {
+            format!("{}/{}", burst.frames.len(), self.burst_count)
+        } else if self.timelapse.is_some() {
+            "Stop".to_string()
+        } else if self.slit_scan.is_some() {
+            "Done".to_string()
+        } else {
+            String::new()
+        };
+
         // Take Picture button (large primary action)
         egui::Area::new("take_picture_btn")
             .fixed_pos(large_center - egui::vec2(sizes.large_radius, sizes.large_radius))
             .order(egui::Order::Background)
             .show(ctx, |ui| {
-                if circular_button_default(ui, sizes.large_radius, "") {
-                    self.capture_and_sort(ctx);
+                if circular_button_default(ui, sizes.large_radius, &shutter_label) {
+                    self.trigger_shutter(ctx);
                 }
             });
 
@@ -84,9 +102,141 @@ impl PixelSorterApp {
             .order(egui::Order::Background)
             .show(ctx, |ui| {
                 if circular_button_default(ui, sizes.small_radius, "Upload") {
-                    self.load_image(ctx);
+                    self.open_image_browser();
+                }
+            });
+
+        // Live pixel-sorted preview toggle (bottom-left corner)
+        egui::Area::new("live_sort_btn")
+            .fixed_pos(egui::pos2(
+                screen_rect.min.x + sizes.spacing,
+                screen_rect.max.y - sizes.small_radius * 2.0 - sizes.spacing,
+            ))
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                let fill = if self.live_sort_preview { button_green() } else { button_fill_normal() };
+                if circular_button(ui, sizes.small_radius, "Live", fill) {
+                    self.live_sort_preview = !self.live_sort_preview;
+                    self.live_sort_frames_to_skip = 0;
+                    self.last_camera_update = None;
                 }
             });
+
+        // Import from USB, above the Live toggle while a drive is attached
+        if !crate::hardware::removable_drives().is_empty() {
+            egui::Area::new("import_btn")
+                .fixed_pos(egui::pos2(
+                    screen_rect.min.x + sizes.spacing,
+                    screen_rect.max.y - sizes.small_radius * 4.0 - sizes.spacing * 2.0,
+                ))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    if circular_button(ui, sizes.small_radius, "Import", button_green()) {
+                        self.start_usb_import();
+                    }
+                });
+        }
+
+        // Capture mode toggle (instant stream grab vs high-quality still)
+        egui::Area::new("capture_mode_btn")
+            .fixed_pos(mode_center - egui::vec2(sizes.small_radius, sizes.small_radius))
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                if circular_button_default(ui, sizes.small_radius, self.capture_mode.name()) {
+                    self.capture_mode = self.capture_mode.next();
+                }
+            });
+
+        // Self-timer toggle (Off / 3s / 5s / 10s)
+        egui::Area::new("self_timer_btn")
+            .fixed_pos(timer_center - egui::vec2(sizes.small_radius, sizes.small_radius))
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                if circular_button_default(ui, sizes.small_radius, self.self_timer.name()) {
+                    self.self_timer = self.self_timer.next();
+                }
+            });
+
+        // Shooting mode toggle (single shot / burst)
+        egui::Area::new("shooting_mode_btn")
+            .fixed_pos(shooting_center - egui::vec2(sizes.small_radius, sizes.small_radius))
+            .order(egui::Order::Background)
+            .show(ctx, |ui| {
+                if circular_button_default(ui, sizes.small_radius, self.shooting_mode.name()) {
+                    self.shooting_mode = self.shooting_mode.next();
+                }
+            });
+
+        // Time-lapse only: sort each frame or keep raw frames
+        if self.shooting_mode == ShootingMode::Timelapse {
+            let sort_label = if self.timelapse_sort_frames { "Sorted" } else { "Raw" };
+            egui::Area::new("timelapse_sort_btn")
+                .fixed_pos(shooting_center - egui::vec2(0.0, small_step) - egui::vec2(sizes.small_radius, sizes.small_radius))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    if circular_button_default(ui, sizes.small_radius, sort_label) {
+                        self.timelapse_sort_frames = !self.timelapse_sort_frames;
+                    }
+                });
+        }
+
+        // Time-lapse only: interval and total length, left of the sort toggle
+        if self.shooting_mode == ShootingMode::Timelapse && self.timelapse.is_none() {
+            let interval_center = shooting_center - egui::vec2(small_step, small_step);
+            egui::Area::new("timelapse_interval_btn")
+                .fixed_pos(interval_center - egui::vec2(sizes.small_radius, sizes.small_radius))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    let label = format!("{}s", self.timelapse_interval.as_secs());
+                    if circular_button_default(ui, sizes.small_radius, &label) {
+                        self.timelapse_interval = next_option(TIMELAPSE_INTERVALS, self.timelapse_interval);
+                    }
+                });
+            egui::Area::new("timelapse_duration_btn")
+                .fixed_pos(interval_center - egui::vec2(small_step, 0.0) - egui::vec2(sizes.small_radius, sizes.small_radius))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    let minutes = self.timelapse_duration.as_secs() / 60;
+                    let label = if minutes >= 60 { format!("{}h", minutes / 60) } else { format!("{}m", minutes) };
+                    if circular_button_default(ui, sizes.small_radius, &label) {
+                        self.timelapse_duration = next_option(TIMELAPSE_DURATIONS, self.timelapse_duration);
+                    }
+                });
+        }
+
+        // Burst only: number of frames, and the interval to its left
+        if self.shooting_mode == ShootingMode::Burst && self.burst.is_none() {
+            let count_center = shooting_center - egui::vec2(0.0, small_step);
+            egui::Area::new("burst_count_btn")
+                .fixed_pos(count_center - egui::vec2(sizes.small_radius, sizes.small_radius))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    if circular_button_default(ui, sizes.small_radius, &format!("{}×", self.burst_count)) {
+                        self.burst_count = next_option(BURST_COUNTS, self.burst_count);
+                    }
+                });
+            egui::Area::new("burst_interval_btn")
+                .fixed_pos(count_center - egui::vec2(small_step, 0.0) - egui::vec2(sizes.small_radius, sizes.small_radius))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    let label = format!("{}s", self.burst_interval_ms as f32 / 1000.0);
+                    if circular_button_default(ui, sizes.small_radius, &label) {
+                        self.burst_interval_ms = next_option(BURST_INTERVALS_MS, self.burst_interval_ms);
+                    }
+                });
+        }
+
+        // Slit-scan only: sample columns or rows
+        if self.shooting_mode == ShootingMode::SlitScan && self.slit_scan.is_none() {
+            egui::Area::new("slit_orientation_btn")
+                .fixed_pos(shooting_center - egui::vec2(0.0, small_step) - egui::vec2(sizes.small_radius, sizes.small_radius))
+                .order(egui::Order::Background)
+                .show(ctx, |ui| {
+                    if circular_button_default(ui, sizes.small_radius, self.slit_orientation.name()) {
+                        self.slit_orientation = self.slit_orientation.next();
+                    }
+                });
+        }
     }
 
     // ============================================================================
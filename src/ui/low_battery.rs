@@ -0,0 +1,84 @@
+use crate::PixelSorterApp;
+use eframe::egui;
+use std::time::Instant;
+
+impl PixelSorterApp {
+    /// Track the UPS shutdown countdown, saving the user's work once when it starts.
+    /// Returns true while the countdown is running.
+    pub fn update_low_battery(&mut self) -> bool {
+        if crate::hardware::low_battery_countdown().is_none() {
+            self.low_battery_save_due = false;
+            if let Some(error) = crate::hardware::take_shutdown_error() {
+                self.low_battery_save_message = None;
+                self.export_message = Some(format!("✗ Low battery, but shutdown failed: {} - shut down manually", error));
+                self.export_message_time = Some(Instant::now());
+            } else if self.low_battery_save_message.take().is_some() {
+                self.export_message = Some("✓ Charging - shutdown cancelled".to_string());
+                self.export_message_time = Some(Instant::now());
+            }
+            return false;
+        }
+
+        if self.low_battery_save_message.is_none() {
+            log::warn!("Low battery shutdown countdown started - saving work");
+            self.low_battery_save_message = Some("Saving your work...".to_string());
+            self.low_battery_save_due = true;
+
+            // The warning must be visible even if the screen was asleep
+            self.is_sleeping = false;
+            self.is_waking = false;
+            self.last_interaction_time = Instant::now();
+        } else if self.low_battery_save_due {
+            self.low_battery_save_due = false;
+            let message = match self.save_session_for_shutdown() {
+                Ok(Some(session_dir)) => format!("Your work was saved to {}", session_dir.display()),
+                Ok(None) => "Nothing to save".to_string(),
+                Err(e) => {
+                    log::error!("Failed to save before shutdown: {}", e);
+                    format!("Could not save your work: {}", e)
+                }
+            };
+            self.low_battery_save_message = Some(message);
+        }
+
+        true
+    }
+
+    /// Full-screen countdown that blocks the rest of the UI until shutdown or cancellation
+    pub fn render_low_battery_overlay(&mut self, ctx: &egui::Context) {
+        let remaining = crate::hardware::low_battery_countdown()
+            .map(|d| d.as_secs_f32().ceil() as u64)
+            .unwrap_or(0);
+        let battery = crate::hardware::get_battery_status();
+        let saved = self.low_battery_save_message.clone().unwrap_or_default();
+
+        egui::CentralPanel::default()
+            .frame(egui::Frame::none())
+            .show(ctx, |ui| {
+                let screen_rect = ui.max_rect();
+
+                // Swallow all touches so nothing underneath can be used
+                let _response = ui.allocate_rect(screen_rect, egui::Sense::click_and_drag());
+
+                ui.painter().rect_filled(screen_rect, 0.0, egui::Color32::from_rgb(40, 0, 0));
+
+                let center = screen_rect.center();
+                let lines = [
+                    ("🔋 Battery low".to_string(), 56.0, egui::Color32::from_rgb(255, 90, 90)),
+                    (format!("Shutting down in {}s", remaining), 96.0, egui::Color32::WHITE),
+                    (format!("{:.2}V", battery.voltage), 28.0, egui::Color32::from_gray(180)),
+                    (saved, 28.0, egui::Color32::from_gray(220)),
+                    ("Plug in the charger to cancel".to_string(), 32.0, egui::Color32::from_rgb(120, 220, 120)),
+                ];
+
+                let mut y = center.y - 200.0;
+                for (text, size, color) in lines {
+                    let galley = ui.painter().layout_no_wrap(text, egui::FontId::proportional(size), color);
+                    ui.painter().galley(egui::pos2(center.x - galley.size().x / 2.0, y), galley);
+                    y += size + 36.0;
+                }
+            });
+
+        ctx.request_repaint_after(std::time::Duration::from_millis(250));
+    }
+}
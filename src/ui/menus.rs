@@ -1,8 +1,11 @@
 use crate::PixelSorterApp;
 use crate::system::SystemControl;
+use crate::hardware::RemovableDrive;
 use eframe::egui;
+use std::path::PathBuf;
 use std::time::Instant;
-use super::styles::{MenuStyle, button_text};
+use super::styles::{MenuStyle, button_green, button_text};
+use crate::session::usb_export::ExportStatus;
 
 const UI_PADDING: f32 = 20.0;
 
@@ -182,10 +185,29 @@ impl PixelSorterApp {
                                        battery.percentage, battery.voltage)
                             };
                             ui.label(button_text(&battery_text, style.label_size));
+
+                            if let Some(estimate) = battery.runtime_estimate {
+                                let label = if battery.is_charging { "until full" } else { "remaining" };
+                                ui.label(button_text(
+                                    &format!("⏳ {} {}", crate::hardware::format_runtime(estimate), label),
+                                    style.label_size,
+                                ));
+                            }
+                            let power = match battery.power_source {
+                                crate::hardware::PowerSource::External => "🔌 Power: External",
+                                crate::hardware::PowerSource::Battery => "🔌 Power: Battery",
+                                crate::hardware::PowerSource::Unknown => "🔌 Power: Unknown",
+                            };
+                            ui.label(button_text(power, style.label_size));
+                            render_battery_chart(ui, style.button_width);
                         } else {
                             ui.label(button_text("🔋 Battery: Not detected", style.label_size));
                         }
 
+                        if let Some(ref error) = self.config_error {
+                            ui.label(egui::RichText::new(format!("⚠ Config: {}", error)).color(egui::Color32::from_rgb(220, 180, 50)).size(style.label_size));
+                        }
+
                         // Current phase
                         ui.label(button_text(&format!("📍 Phase: {:?}", self.current_phase), style.label_size));
 
@@ -193,6 +215,9 @@ impl PixelSorterApp {
                         if let Some(ref session) = self.current_session_folder {
                             ui.label(button_text(&format!("📁 Session: {}", session), style.label_size));
                             ui.label(button_text(&format!("🔢 Iteration: {}", self.iteration_counter), style.label_size));
+                            if self.image_writer.pending() > 0 {
+                                ui.label(button_text(&format!("💾 Saving: {} pending", self.image_writer.pending()), style.label_size));
+                            }
                         }
                     });
 
@@ -220,7 +245,7 @@ impl PixelSorterApp {
                                 self.show_developer_menu = false;
 
                                 // Pull updates and restart service using update_manager
-                                let _ = self.update_manager.pull_and_restart_service("pixelsort-kiosk");
+                                let _ = self.update_manager.pull_and_restart_service(&self.config.update.service_name);
                             }
                         } else {
                             ui.label(egui::RichText::new("✅ App is up to date").color(egui::Color32::GRAY).size(style.label_size));
@@ -253,6 +278,16 @@ impl PixelSorterApp {
                             }
                         }
 
+                        ui.add_space(style.spacing * 0.5);
+
+                        // Settings screen
+                        if ui.add_sized(
+                            [style.button_width, style.button_height], 
+                            egui::Button::new(button_text("⚙ Settings", style.label_size))
+                        ).clicked() {
+                            self.open_settings();
+                        }
+
                         ui.add_space(style.spacing * 0.5);                        // Clear session
                         if ui.add_sized(
                             [style.button_width, style.button_height], 
@@ -317,11 +352,62 @@ impl PixelSorterApp {
         }
     }
 
+    /// React to drives being attached or removed
+    pub fn poll_storage_events(&mut self) {
+        for event in crate::hardware::take_storage_events() {
+            match event {
+                crate::hardware::StorageEvent::Added(drive) if drive.writable => {
+                    self.export_message = Some(format!("✓ USB drive connected: {}", drive.label));
+                    if !self.show_usb_export_dialog && self.usb_export_job.is_none() {
+                        log::info!("USB mounted - opening export dialog");
+                        self.show_usb_export_dialog = true;
+                        self.usb_export_drive = Some(drive.mount_point);
+                    }
+
+                    // Attaching a drive counts as interaction
+                    if self.is_sleeping {
+                        self.is_sleeping = false;
+                        self.is_waking = true;
+                        self.wake_start_time = Some(Instant::now());
+                    }
+                    self.last_interaction_time = Instant::now();
+                }
+                crate::hardware::StorageEvent::Added(drive) => {
+                    self.export_message = Some(format!("⚠ {} is read-only: images can be imported but not exported", drive.label));
+                }
+                crate::hardware::StorageEvent::Removed(drive) => {
+                    if self.usb_export_drive.as_ref() == Some(&drive.mount_point) {
+                        self.usb_export_drive = None;
+                    }
+                    // Ejecting after an export removes the drive too; the dialog already says so
+                    if self.usb_export_job.is_some() || self.usb_export_outcome.is_some() {
+                        continue;
+                    }
+                    self.export_message = Some(format!("⏏ USB drive removed: {}", drive.label));
+                }
+            }
+            self.export_message_time = Some(Instant::now());
+        }
+    }
+
     pub fn render_usb_export_dialog(&mut self, ctx: &egui::Context) {
         if !self.show_usb_export_dialog {
             return;
         }
 
+        let drives: Vec<_> = crate::hardware::removable_drives().into_iter().filter(|drive| drive.writable).collect();
+        let busy = self.usb_export_job.is_some() || self.usb_export_outcome.is_some();
+        if drives.is_empty() && !busy {
+            // Every drive was unplugged while the dialog was open
+            self.show_usb_export_dialog = false;
+            self.usb_export_drive = None;
+            self.reset_export_selection();
+            return;
+        }
+        if !drives.iter().any(|drive| Some(&drive.mount_point) == self.usb_export_drive.as_ref()) {
+            self.usb_export_drive = drives.first().map(|drive| drive.mount_point.clone());
+        }
+
         let style = MenuStyle::usb_export();
 
         let response = egui::Window::new("💾 Export to USB")
@@ -333,76 +419,262 @@ impl PixelSorterApp {
 
                 ui.vertical_centered(|ui| {
                     ui.add_space(style.spacing * 0.67);
-                    
-                    ui.label(button_text("📁 Copy all sorted images to USB?", style.heading_size));
-                    
-                    ui.add_space(style.spacing);
-                    
-                    // Checkbox for delete after copy
-                    ui.horizontal(|ui| {
-                        ui.add_space((style.width - 320.0) / 2.0); // Center the checkbox
-                        ui.checkbox(&mut self.usb_export_delete_after, 
-                            button_text("Delete images after copying", style.label_size * 0.9));
-                    });
-                    
-                    ui.add_space(style.spacing * 1.33);
-                    
-                    // Copy button
-                    if ui.add_sized(
-                        [style.button_width, style.button_height], 
-                        egui::Button::new(button_text("✓ Copy to USB", style.label_size))
-                            .fill(egui::Color32::from_rgb(40, 120, 40)))
-                        .clicked() 
-                    {
-                        log::info!("USB export started (delete_after: {})", self.usb_export_delete_after);
+
+                    if let Some(job) = &self.usb_export_job {
+                        // Copy in progress
+                        let progress = job.progress();
+                        let heading = match progress.status {
+                            ExportStatus::Syncing => "💾 Finishing writes...".to_string(),
+                            ExportStatus::Verifying => format!("🔍 Verifying {} of {} files...",
+                                (progress.files_verified + progress.mismatches.len() + 1).min(progress.files_total), progress.files_total),
+                            ExportStatus::Deleting => "🗑 Deleting verified local copies...".to_string(),
+                            ExportStatus::Ejecting => format!("⏏ Ejecting {}...", self.usb_export_label),
+                            _ => format!("📁 Copying to {}...", self.usb_export_label),
+                        };
+                        ui.label(button_text(&heading, style.heading_size * 0.8));
+                        ui.add_space(style.spacing);
+
+                        ui.add(egui::ProgressBar::new(progress.fraction())
+                            .desired_width(style.button_width)
+                            .show_percentage());
+                        ui.label(button_text(
+                            &format!("File {} of {} · {:.1} / {:.1} MB",
+                                (progress.files_done + 1).min(progress.files_total), progress.files_total,
+                                progress.bytes_done as f64 / 1e6, progress.bytes_total as f64 / 1e6),
+                            style.label_size * 0.9,
+                        ));
+                        ui.label(button_text(&progress.current_file, style.label_size * 0.8).color(egui::Color32::GRAY));
+
+                        ui.add_space(style.spacing * 1.33);
+
+                        // Cancelling only makes sense before anything local is deleted
+                        let can_cancel = matches!(progress.status, ExportStatus::Copying | ExportStatus::Verifying);
+                        if ui.add_enabled(can_cancel, egui::Button::new(button_text("Cancel", style.label_size))
+                            .min_size(egui::vec2(style.button_width, style.cancel_button_height)))
+                            .clicked()
+                        {
+                            job.cancel();
+                        }
+
+                        ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                    } else if let Some(outcome) = self.usb_export_outcome.clone() {
+                        // Finished: stays up until dismissed so the "safe to remove" note isn't missed
+                        for line in outcome.lines() {
+                            ui.label(button_text(line, style.label_size));
+                        }
+
+                        ui.add_space(style.spacing * 1.33);
+
+                        if ui.add_sized(
+                            [style.button_width, style.button_height],
+                            egui::Button::new(button_text("Done", style.label_size)))
+                            .clicked()
+                        {
+                            self.usb_export_outcome = None;
+                            self.show_usb_export_dialog = false;
+                        }
+                    } else {
+                        ui.label(button_text("📁 Copy sorted images to USB", style.heading_size));
                         
-                        match self.copy_to_usb() {
-                            Ok(()) => {
-                                self.export_message = Some("✓ Exported to USB!".to_string());
-                                
-                                // Delete files if checkbox was checked
-                                if self.usb_export_delete_after {
-                                    if let Err(e) = std::fs::remove_dir_all("sorted_images") {
-                                        log::error!("Failed to delete images: {}", e);
-                                        self.export_message = Some(format!("⚠ Exported but deletion failed: {}", e));
-                                    } else {
-                                        log::info!("Deleted local sorted_images folder after export");
-                                        self.export_message = Some("✓ Exported and deleted local copies!".to_string());
-                                        // Reset session since we deleted everything
-                                        self.current_session_folder = None;
-                                        self.iteration_counter = 0;
-                                    }
-                                }
-                                
-                                self.export_message_time = Some(Instant::now());
-                            }
-                            Err(e) => {
-                                self.export_message = Some(format!("✗ Export failed: {}", e));
-                                self.export_message_time = Some(Instant::now());
-                            }
+                        ui.add_space(style.spacing);
+
+                        self.render_export_selection(ui, &style);
+
+                        ui.add_space(style.spacing);
+                        
+                        // Drive picker when more than one drive is attached
+                        if drives.len() > 1 {
+                            drive_picker(ui, &style, &drives, &mut self.usb_export_drive);
+                        } else {
+                            ui.label(button_text(&format!("💾 {}", drives[0].description()), style.label_size * 0.9));
                         }
                         
-                        self.show_usb_export_dialog = false;
-                        self.usb_export_delete_after = false; // Reset for next time
+                        ui.add_space(style.spacing);
+                        
+                        // Checkboxes for delete after copy and ZIP archives
+                        ui.horizontal(|ui| {
+                            ui.add_space((style.width - 320.0) / 2.0); // Center the checkbox
+                            ui.checkbox(&mut self.usb_export_delete_after, 
+                                button_text("Delete images after copying", style.label_size * 0.9));
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add_space((style.width - 320.0) / 2.0);
+                            ui.checkbox(&mut self.usb_export_zip,
+                                button_text("ZIP archive per session", style.label_size * 0.9));
+                        });
+                        
+                        ui.add_space(style.spacing * 1.33);
+                        
+                        // Copy button
+                        let anything_selected = self.usb_export_selected.as_ref().is_some_and(|selected| !selected.is_empty());
+                        if ui.add_enabled(anything_selected, egui::Button::new(button_text("✓ Copy to USB", style.label_size))
+                                .fill(egui::Color32::from_rgb(40, 120, 40))
+                                .min_size(egui::vec2(style.button_width, style.button_height)))
+                            .clicked() 
+                        {
+                            let drive = drives
+                                .iter()
+                                .find(|drive| self.usb_export_drive.as_ref() == Some(&drive.mount_point))
+                                .unwrap_or(&drives[0])
+                                .clone();
+                            self.start_usb_export(&drive);
+                        }
+                        
+                        ui.add_space(style.spacing * 0.67);
+
+                        // Import from the same drive
+                        if ui.add_sized(
+                            [style.button_width, style.cancel_button_height],
+                            egui::Button::new(button_text("📥 Import images from USB", style.label_size)))
+                            .clicked()
+                        {
+                            let drive = drives
+                                .iter()
+                                .find(|drive| self.usb_export_drive.as_ref() == Some(&drive.mount_point))
+                                .unwrap_or(&drives[0])
+                                .clone();
+                            self.reset_export_selection();
+                            self.open_usb_import(&drive);
+                        }
+
+                        ui.add_space(style.spacing * 0.67);
+                        
+                        // Cancel button
+                        if ui.add_sized(
+                            [style.button_width, style.cancel_button_height], 
+                            egui::Button::new(button_text("Cancel", style.label_size)))
+                            .clicked() 
+                        {
+                            self.show_usb_export_dialog = false;
+                            self.usb_export_delete_after = self.config.export.delete_after_copy; // Reset
+                            self.reset_export_selection();
+                        }
+                    }
+                });
+            });
+        
+        // Force window to top layer
+        if let Some(response) = response {
+            ctx.move_to_top(response.response.layer_id);
+        }
+    }
+
+    /// Import from USB: straight to the browser with one drive, otherwise pick one first.
+    /// Read-only drives are listed too, since importing only reads.
+    pub fn start_usb_import(&mut self) {
+        match crate::hardware::removable_drives().as_slice() {
+            [] => {}
+            [drive] => self.open_usb_import(drive),
+            drives => self.usb_import_drive = drives.first().map(|drive| drive.mount_point.clone()),
+        }
+    }
+
+    pub fn render_usb_import_picker(&mut self, ctx: &egui::Context) {
+        if self.usb_import_drive.is_none() {
+            return;
+        }
+
+        let drives = crate::hardware::removable_drives();
+        if !drives.iter().any(|drive| Some(&drive.mount_point) == self.usb_import_drive.as_ref()) {
+            self.usb_import_drive = drives.first().map(|drive| drive.mount_point.clone());
+            if drives.is_empty() {
+                return;
+            }
+        }
+
+        let style = MenuStyle::usb_export();
+
+        egui::Window::new("📥 Import from USB")
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.set_min_width(style.width);
+
+                ui.vertical_centered(|ui| {
+                    ui.add_space(style.spacing * 0.67);
+                    drive_picker(ui, &style, &drives, &mut self.usb_import_drive);
+                    ui.add_space(style.spacing * 1.33);
+
+                    if ui.add_sized(
+                        [style.button_width, style.button_height],
+                        egui::Button::new(button_text("📥 Import images", style.label_size))
+                            .fill(button_green()))
+                        .clicked()
+                    {
+                        let drive = drives
+                            .iter()
+                            .find(|drive| self.usb_import_drive.as_ref() == Some(&drive.mount_point))
+                            .unwrap_or(&drives[0])
+                            .clone();
+                        self.usb_import_drive = None;
+                        self.open_usb_import(&drive);
                     }
-                    
+
                     ui.add_space(style.spacing * 0.67);
-                    
-                    // Cancel button
+
                     if ui.add_sized(
-                        [style.button_width, style.cancel_button_height], 
+                        [style.button_width, style.cancel_button_height],
                         egui::Button::new(button_text("Cancel", style.label_size)))
-                        .clicked() 
+                        .clicked()
                     {
-                        self.show_usb_export_dialog = false;
-                        self.usb_export_delete_after = false; // Reset
+                        self.usb_import_drive = None;
                     }
                 });
             });
-        
-        // Force window to top layer
-        if let Some(response) = response {
-            ctx.move_to_top(response.response.layer_id);
+    }
+}
+
+/// One selectable row per drive, shared by the export dialog and the import picker
+fn drive_picker(ui: &mut egui::Ui, style: &MenuStyle, drives: &[RemovableDrive], selected: &mut Option<PathBuf>) {
+    ui.label(button_text("Choose a drive:", style.label_size));
+    for drive in drives {
+        let is_selected = selected.as_ref() == Some(&drive.mount_point);
+        if ui.add_sized(
+            [style.button_width, style.cancel_button_height],
+            egui::SelectableLabel::new(is_selected, button_text(&drive.description(), style.label_size * 0.9)),
+        ).clicked() {
+            *selected = Some(drive.mount_point.clone());
         }
     }
 }
+
+const BATTERY_CHART_HEIGHT: f32 = 120.0;
+const BATTERY_CHART_SPAN_SECS: u64 = 6 * 60 * 60;  // Show the last 6 hours
+
+/// Percentage over time from the battery history (green while charging)
+fn render_battery_chart(ui: &mut egui::Ui, width: f32) {
+    let history = crate::hardware::get_battery_history();
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(width, BATTERY_CHART_HEIGHT), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+
+    painter.rect_filled(rect, 4.0, egui::Color32::from_black_alpha(120));
+    for level in [25.0, 50.0, 75.0] {
+        let y = rect.bottom() - rect.height() * level / 100.0;
+        painter.hline(rect.x_range(), y, egui::Stroke::new(1.0, egui::Color32::from_white_alpha(20)));
+    }
+
+    let Some(latest) = history.last().map(|s| s.timestamp) else {
+        return;
+    };
+    let start = latest.saturating_sub(BATTERY_CHART_SPAN_SECS);
+    let to_pos = |timestamp: u64, percentage: f32| {
+        egui::pos2(
+            rect.left() + rect.width() * (timestamp.saturating_sub(start)) as f32 / BATTERY_CHART_SPAN_SECS as f32,
+            rect.bottom() - rect.height() * percentage.clamp(0.0, 100.0) / 100.0,
+        )
+    };
+
+    let visible: Vec<_> = history.iter().filter(|s| s.timestamp >= start).collect();
+    for pair in visible.windows(2) {
+        let color = if pair[1].is_charging {
+            egui::Color32::from_rgb(100, 200, 100)
+        } else {
+            egui::Color32::from_rgb(220, 220, 220)
+        };
+        painter.line_segment(
+            [to_pos(pair[0].timestamp, pair[0].percentage), to_pos(pair[1].timestamp, pair[1].percentage)],
+            egui::Stroke::new(2.0, color),
+        );
+    }
+}
@@ -1,11 +1,17 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 use eframe::egui;
 use tokio::sync::RwLock;
 
-use crate::system::UpdateManager;
+use crate::system::{AppConfig, ConfigWatcher, UpdateManager};
 use crate::processing::{PixelSorter, SortingAlgorithm, SortingParameters};
-use crate::hardware::CameraController;
+use crate::processing::slit_scan::{SlitScan, SlitOrientation};
+use crate::hardware::{CameraConfig, CameraController, CaptureMode};
+use crate::session::library::{ExportMode, Session};
+use crate::session::usb_export::UsbExportJob;
+use crate::session::writer::ImageWriter;
 
 // Module declarations
 mod state;
@@ -18,9 +24,17 @@ mod menus;
 mod viewport;
 mod styles;
 mod camera;
+mod timelapse;
+mod slit_scan;
+mod low_battery;
+mod config_reload;
+mod settings;
+mod export_selection;
+mod image_browser;
+mod disk_usage;
 
 // Re-export public types
-pub use state::{Phase, DragState};
+pub use state::{Phase, DragState, ShootingMode, SelfTimer, BurstState, TimelapseState};
 
 // ============================================================================
 // MAIN APP STRUCT
@@ -46,6 +60,31 @@ pub struct PixelSorterApp {
     pub camera_controller: Option<Arc<RwLock<CameraController>>>,
     pub last_camera_update: Option<Instant>,
     pub preview_mode: bool,
+    pub capture_mode: CaptureMode,
+    pub live_sort_preview: bool,
+    pub live_sort_cost_ms: f32,
+    pub live_sort_frames_to_skip: u32,
+    pub shooting_mode: ShootingMode,
+    pub self_timer: SelfTimer,
+    pub countdown_start: Option<Instant>,
+    
+    // Burst capture
+    pub burst: Option<BurstState>,
+    pub burst_count: u32,
+    pub burst_interval_ms: u64,
+    pub burst_frames: Vec<image::RgbImage>,
+    pub burst_thumbnails: Vec<egui::TextureHandle>,
+    
+    // Time-lapse capture
+    pub timelapse: Option<TimelapseState>,
+    pub timelapse_interval: std::time::Duration,
+    pub timelapse_duration: std::time::Duration,
+    pub timelapse_sort_frames: bool,
+    pub timelapse_result: Option<std::sync::mpsc::Receiver<Result<std::path::PathBuf, String>>>,
+    
+    // Slit-scan capture
+    pub slit_scan: Option<SlitScan>,
+    pub slit_orientation: SlitOrientation,
     
     // Crop state
     pub crop_rect: Option<egui::Rect>, // In image coordinates
@@ -54,6 +93,8 @@ pub struct PixelSorterApp {
     // Session management
     pub iteration_counter: u32,
     pub current_session_folder: Option<String>,
+    // Saves iterations in the background
+    pub image_writer: ImageWriter,
     
     // Export status
     pub export_message: Option<String>,
@@ -82,17 +123,53 @@ pub struct PixelSorterApp {
     
     // Shutdown menu
     pub show_shutdown_menu: bool,
-    // Track previous USB presence to detect new mounts
-    pub prev_usb_present: bool,
-    pub last_usb_check: Option<Instant>,
+    // Set once work has been saved for a low-battery shutdown (shown in the warning overlay)
+    pub low_battery_save_message: Option<String>,
+    // The save runs on the frame after the overlay first shows "Saving", as it can block for a while
+    pub low_battery_save_due: bool,
 
     // USB export dialog
     pub show_usb_export_dialog: bool,
     pub usb_export_delete_after: bool,
+    // Mount point of the drive picked in the export dialog
+    pub usb_export_drive: Option<PathBuf>,
+    // Background copy in progress, and its result until the dialog is dismissed
+    pub usb_export_job: Option<UsbExportJob>,
+    pub usb_export_label: String,
+    pub usb_export_outcome: Option<String>,
+    // What to export: sessions on disk (scanned when the dialog opens) and the chosen files
+    pub usb_export_mode: ExportMode,
+    pub usb_export_zip: bool,
+    pub usb_export_sessions: Option<Vec<Session>>,
+    pub usb_export_selected: Option<BTreeSet<PathBuf>>,
+    // Session whose iterations are listed
+    pub usb_export_expanded: Option<String>,
+
+    // Drive picked to import from, while the import drive picker is open
+    pub usb_import_drive: Option<PathBuf>,
+    // Image browser for opening local images or importing from a drive
+    pub image_browser: Option<state::BrowserState>,
     
     // Developer menu
     pub show_developer_menu: bool,
     
+    // Settings screen (edits a copy of the config until saved)
+    pub show_settings: bool,
+    pub settings_draft: AppConfig,
+    
+    // Configuration (pixelsort.toml)
+    pub config: AppConfig,
+    pub config_error: Option<String>,
+    pub config_watcher: ConfigWatcher,
+    pub last_config_check: Option<Instant>,
+    // Camera settings waiting for a capture to release the camera lock
+    pub pending_camera_config: Option<CameraConfig>,
+
+    // Disk space and retention, checked once a minute
+    pub last_disk_check: Option<Instant>,
+    // Free bytes while below the low-space threshold
+    pub low_disk_space: Option<u64>,
+    
     // Other
     pub tint_enabled: bool,
 }
@@ -105,6 +182,8 @@ impl PixelSorterApp {
     pub fn new(
         pixel_sorter: Arc<PixelSorter>,
         camera_controller: Option<Arc<RwLock<CameraController>>>,
+        config: AppConfig,
+        config_error: Option<String>,
     ) -> Self {
         // Start camera streaming if available
         if let Some(ref camera) = camera_controller {
@@ -126,10 +205,30 @@ impl PixelSorterApp {
             camera_controller,
             last_camera_update: None,
             preview_mode: true,
+            capture_mode: CaptureMode::Stream,
+            live_sort_preview: false,
+            live_sort_cost_ms: 0.0,
+            live_sort_frames_to_skip: 0,
+            shooting_mode: ShootingMode::Single,
+            self_timer: SelfTimer::Off,
+            countdown_start: None,
+            burst: None,
+            burst_count: 5,
+            burst_interval_ms: 500,
+            burst_frames: Vec::new(),
+            burst_thumbnails: Vec::new(),
+            timelapse: None,
+            timelapse_interval: std::time::Duration::from_secs(10),
+            timelapse_duration: std::time::Duration::from_secs(30 * 60),
+            timelapse_sort_frames: true,
+            timelapse_result: None,
+            slit_scan: None,
+            slit_orientation: SlitOrientation::Column,
             crop_rect: None,
             drag_state: DragState::None,
             iteration_counter: 0,
             current_session_folder: None,
+            image_writer: ImageWriter::new(),
             export_message: None,
             export_message_time: None,
             show_splash: true,
@@ -142,53 +241,42 @@ impl PixelSorterApp {
             wake_start_time: None,
             last_interaction_time: Instant::now(),
             sleep_logo: None,
-            update_manager: UpdateManager::new("/home/pixelsort/Pixelsort".to_string()),
+            update_manager: UpdateManager::new(config.update.install_dir.clone()),
             update_check_time: None,
             startup_check_done: false,
             show_shutdown_menu: false,
-            prev_usb_present: false,
-            last_usb_check: None,
+            low_battery_save_message: None,
+            low_battery_save_due: false,
             show_usb_export_dialog: false,
-            usb_export_delete_after: false,
+            usb_export_delete_after: config.export.delete_after_copy,
+            usb_export_drive: None,
+            usb_export_job: None,
+            usb_export_label: String::new(),
+            usb_export_outcome: None,
+            usb_export_mode: config.export_mode(),
+            usb_export_zip: config.export.zip_per_session,
+            usb_export_sessions: None,
+            usb_export_selected: None,
+            usb_export_expanded: None,
+            usb_import_drive: None,
+            image_browser: None,
             show_developer_menu: false,
+            show_settings: false,
+            settings_draft: AppConfig::default(),
+            config,
+            config_error,
+            config_watcher: ConfigWatcher::new(),
+            last_config_check: None,
+            pending_camera_config: None,
+            last_disk_check: None,
+            low_disk_space: None,
             tint_enabled: false,
         }
     }
 
+    /// True when a writable removable drive is mounted
     fn usb_present(&self) -> bool {
-        #[cfg(target_os = "linux")]
-        {
-            use std::process::Command;
-            
-            // Check mounted filesystems for USB drives (exfat, vfat, ntfs)
-            if let Ok(output) = Command::new("mount").output() {
-                let mount_output = String::from_utf8_lossy(&output.stdout);
-                
-                // Look for USB filesystem types mounted under /media/
-                for line in mount_output.lines() {
-                    if line.contains("/media/") && 
-                       (line.contains("exfat") || line.contains("vfat") || line.contains("ntfs")) {
-                        // Extract the mount point (between "on " and " type")
-                        if let Some(on_idx) = line.find(" on ") {
-                            if let Some(type_idx) = line.find(" type ") {
-                                let mount_point = &line[on_idx + 4..type_idx];
-                                log::info!("Found USB drive at: {}", mount_point);
-                                
-                                // Test if we can write to it
-                                let test_path = std::path::PathBuf::from(mount_point)
-                                    .join(".pixelsort_usb_check");
-                                if std::fs::write(&test_path, "test").is_ok() {
-                                    let _ = std::fs::remove_file(&test_path);
-                                    return true;
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        false
+        crate::hardware::removable_drives().iter().any(|drive| drive.writable)
     }
 }
 
@@ -205,6 +293,12 @@ impl eframe::App for PixelSorterApp {
             return;
         }
         
+        // Low battery countdown blocks everything else until shutdown or charging resumes
+        if self.update_low_battery() {
+            self.render_low_battery_overlay(ctx);
+            return;
+        }
+        
         // Hide cursor in kiosk mode - force it every frame
         ctx.set_cursor_icon(egui::CursorIcon::None);
         ctx.output_mut(|o| o.cursor_icon = egui::CursorIcon::None);
@@ -242,12 +336,18 @@ impl eframe::App for PixelSorterApp {
                 }
             });
         
-        // Show splash screen for 2 seconds
+        // Show splash screen (2 seconds by default)
         if self.show_splash {
             if let Some(start_time) = self.splash_start_time {
                 let elapsed = start_time.elapsed().as_secs_f32();
-                if elapsed > 2.0 {
+                if elapsed > self.config.display.splash_secs {
                     self.show_splash = false;
+                    
+                    // Report a broken config file once the UI is visible
+                    if let Some(error) = &self.config_error {
+                        self.export_message = Some(format!("✗ Config error: {}", error));
+                        self.export_message_time = Some(Instant::now());
+                    }
                 } else {
                     self.render_splash_screen(ctx, elapsed);
                     ctx.request_repaint();
@@ -300,9 +400,27 @@ impl eframe::App for PixelSorterApp {
             }
         }
         
-        // Sleep mode check (5 minutes)
+        // Report finished time-lapse assembly and failed background saves
+        self.poll_timelapse_result();
+        self.poll_image_writes();
+        
+        // Toast power loss / restore reported by the UPS monitor
+        self.poll_power_events();
+        
+        // Open the export dialog when a USB drive is attached
+        self.poll_storage_events();
+        self.poll_usb_export();
+        
+        // Pick up edits to pixelsort.toml
+        self.poll_config_reload(ctx);
+
+        // Free space warning and pruning of old sessions
+        self.poll_disk_usage();
+        
+        // Sleep mode check (5 minutes by default, never while a time-lapse is recording or a USB export runs)
         let idle_duration = self.last_interaction_time.elapsed().as_secs();
-        if !self.is_sleeping && idle_duration >= 300 {
+        let sleep_timeout = self.config.display.sleep_timeout_secs;
+        if !self.is_sleeping && sleep_timeout > 0 && idle_duration >= sleep_timeout && self.timelapse.is_none() && self.usb_export_job.is_none() && self.image_browser.is_none() {
             self.is_sleeping = true;
         }
         
@@ -320,9 +438,20 @@ impl eframe::App for PixelSorterApp {
             return;
         }
         
+        // Time-lapse frames are only taken in Input phase; leaving it ends the recording
+        if self.current_phase != Phase::Input && self.timelapse.is_some() {
+            log::info!("Left the camera screen - stopping time-lapse");
+            self.stop_timelapse();
+        }
+
         // Update camera preview at 30 FPS if in Input phase
         if self.current_phase == Phase::Input && !self.is_processing {
-            self.update_camera_preview(ctx);
+            self.update_capture_sequence(ctx);
+            if self.slit_scan.is_some() {
+                self.update_slit_scan(ctx);
+            } else {
+                self.update_camera_preview(ctx);
+            }
             ctx.request_repaint();
         }
 
@@ -331,10 +460,16 @@ impl eframe::App for PixelSorterApp {
         
         // Render overlays AFTER CentralPanel so they appear on top
         let full_rect = ctx.screen_rect();
+        self.render_countdown_overlay(ctx, full_rect);
+        self.render_status_banners(ctx);
+        self.render_burst_picker(ctx);
         self.render_battery_indicator(ctx, full_rect);
         self.render_shutdown_button(ctx, full_rect);
         self.render_developer_menu(ctx, full_rect);
+        self.render_settings(ctx);
         self.render_usb_export_dialog(ctx);
+        self.render_usb_import_picker(ctx);
+        self.render_image_browser(ctx);
         self.render_export_message(ctx, full_rect);
     }
 }
@@ -351,7 +486,14 @@ impl PixelSorterApp {
             if let Some(camera) = self.camera_controller.clone() {
                 if let Ok(mut camera_lock) = camera.try_write() {
                     if let Ok(preview_image) = camera_lock.get_fast_preview_image() {
-                        self.update_camera_texture(ctx, &preview_image);
+                        if self.live_sort_preview {
+                            // Skipped frames keep showing the last sorted frame
+                            if let Some(sorted) = self.sort_preview_frame(&preview_image) {
+                                self.update_camera_texture(ctx, &sorted);
+                            }
+                        } else {
+                            self.update_camera_texture(ctx, &preview_image);
+                        }
                         self.last_camera_update = Some(now);
                     }
                 }
@@ -365,23 +507,6 @@ impl PixelSorterApp {
             .show(ctx, |ui| {
                 let full_rect = ui.max_rect();
 
-                // Detect USB mount edge (throttled to once per 2 seconds to avoid overhead)
-                let now = Instant::now();
-                let should_check_usb = match self.last_usb_check {
-                    None => true,
-                    Some(last) => now.duration_since(last) >= std::time::Duration::from_secs(2),
-                };
-                
-                if should_check_usb {
-                    let usb_now = self.usb_present();
-                    if usb_now && !self.prev_usb_present && !self.show_usb_export_dialog {
-                        log::info!("USB mounted - opening export dialog");
-                        self.show_usb_export_dialog = true;
-                    }
-                    self.prev_usb_present = usb_now;
-                    self.last_usb_check = Some(now);
-                }
-
                 self.render_viewport(ui, full_rect, ctx);
                 self.render_button_overlay(ui, ctx, full_rect);
             });
@@ -0,0 +1,300 @@
+use crate::PixelSorterApp;
+use crate::session::library::ExportMode;
+use crate::system::{AppConfig, ConfigWatcher};
+use crate::system::config::{MAX_SHARE_SIZE, MAX_SOC_HYSTERESIS, MAX_WARNING_SECS, MIN_SHARE_SIZE, MIN_WARNING_SECS};
+use eframe::egui;
+use std::path::PathBuf;
+use std::time::Instant;
+use super::styles::{MenuStyle, button_text, button_green};
+
+const CAPTURE_SIZES: &[((u32, u32), &str)] = &[
+    ((1024, 600), "Screen"),
+    ((1920, 1080), "1080p"),
+    ((2028, 1520), "2 MP"),
+    ((4056, 3040), "12 MP"),
+];
+const PREVIEW_SIZES: &[((u32, u32), &str)] = &[
+    ((640, 480), "640×480"),
+    ((1024, 600), "1024×600"),
+    ((1280, 720), "1280×720"),
+];
+const ROTATIONS: &[(u32, &str)] = &[(0, "0°"), (90, "90°"), (180, "180°"), (270, "270°")];
+const SLEEP_TIMEOUTS: &[(u64, &str)] = &[
+    (0, "Never"),
+    (60, "1 min"),
+    (120, "2 min"),
+    (300, "5 min"),
+    (600, "10 min"),
+    (1800, "30 min"),
+];
+const OUTPUT_FORMATS: &[(&str, &str)] = &[("png", "PNG"), ("jpeg", "JPEG"), ("webp", "WebP"), ("tiff", "TIFF")];
+const PNG_COMPRESSIONS: &[(&str, &str)] = &[("fast", "Fast"), ("default", "Balanced"), ("best", "Smallest")];
+
+impl PixelSorterApp {
+    pub fn open_settings(&mut self) {
+        self.settings_draft = self.config.clone();
+        self.show_settings = true;
+        self.show_developer_menu = false;
+    }
+
+    /// Touch-friendly editor for pixelsort.toml. Changes apply and persist on Save.
+    pub fn render_settings(&mut self, ctx: &egui::Context) {
+        if !self.show_settings {
+            return;
+        }
+
+        let style = MenuStyle::settings();
+        let mut save = false;
+        let mut close = false;
+
+        let response = egui::Window::new("⚙ Settings")
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.set_min_width(style.width);
+
+                // Bigger hit targets for fingers
+                let spacing = ui.spacing_mut();
+                spacing.interact_size.y = 48.0;
+                spacing.slider_width = style.button_width - 260.0;
+                spacing.icon_width = 36.0;
+                spacing.icon_width_inner = 24.0;
+                spacing.item_spacing = egui::vec2(12.0, 12.0);
+
+                let draft = &mut self.settings_draft;
+                egui::ScrollArea::vertical()
+                    .max_height(ctx.screen_rect().height() * 0.7)
+                    .show(ui, |ui| {
+                        section(ui, &style, "📷 Camera", |ui| {
+                            let camera = &mut draft.camera;
+                            let mut capture = (camera.capture_width, camera.capture_height);
+                            choice_row(ui, &style, "Photo size", &mut capture, CAPTURE_SIZES);
+                            (camera.capture_width, camera.capture_height) = capture;
+
+                            let mut preview = (camera.preview_width, camera.preview_height);
+                            choice_row(ui, &style, "Live view size", &mut preview, PREVIEW_SIZES);
+                            (camera.preview_width, camera.preview_height) = preview;
+
+                            ui.add(egui::Slider::new(&mut camera.quality, 1..=100)
+                                .text(button_text("JPEG quality", style.label_size)));
+                            choice_row(ui, &style, "Rotation", &mut camera.rotation, ROTATIONS);
+                            ui.checkbox(&mut camera.flip_horizontal, button_text("Mirror horizontally", style.label_size));
+                            ui.checkbox(&mut camera.flip_vertical, button_text("Flip vertically", style.label_size));
+                        });
+
+                        section(ui, &style, "🖥 Display", |ui| {
+                            choice_row(ui, &style, "Sleep after", &mut draft.display.sleep_timeout_secs, SLEEP_TIMEOUTS);
+                            ui.add(egui::Slider::new(&mut draft.display.splash_secs, 0.0..=10.0)
+                                .step_by(0.5)
+                                .suffix(" s")
+                                .text(button_text("Splash screen", style.label_size)));
+                            ui.checkbox(&mut draft.display.fullscreen, button_text("Fullscreen", style.label_size));
+                        });
+
+                        section(ui, &style, "📁 Storage", |ui| {
+                            ui.label(button_text("Save images to", style.label_size));
+                            let mut output_dir = draft.paths.output_dir.display().to_string();
+                            ui.horizontal(|ui| {
+                                let edit = egui::TextEdit::singleline(&mut output_dir)
+                                    .font(egui::FontId::proportional(style.label_size))
+                                    .desired_width(style.button_width - 200.0);
+                                if ui.add(edit).changed() {
+                                    draft.paths.output_dir = PathBuf::from(&output_dir);
+                                }
+                                if ui.add_sized([180.0, 48.0], egui::Button::new(button_text("Default", style.label_size))).clicked() {
+                                    draft.paths.output_dir = AppConfig::default().paths.output_dir;
+                                }
+                            });
+
+                            let storage = &mut draft.storage;
+                            ui.add(egui::Slider::new(&mut storage.low_space_mb, 0..=5000)
+                                .step_by(100.0)
+                                .suffix(" MB")
+                                .text(button_text("Warn below", style.label_size)));
+                            ui.label(button_text("Keep at most (0 = no limit)", style.label_size));
+                            ui.add(egui::Slider::new(&mut storage.max_sessions, 0..=500)
+                                .text(button_text("Sessions", style.label_size)));
+                            ui.add(egui::Slider::new(&mut storage.max_age_days, 0..=365)
+                                .suffix(" days")
+                                .text(button_text("Age", style.label_size)));
+                            ui.add(egui::Slider::new(&mut storage.max_total_mb, 0..=64000)
+                                .step_by(500.0)
+                                .suffix(" MB")
+                                .text(button_text("Total size", style.label_size)));
+                            ui.checkbox(&mut storage.prune_unexported,
+                                button_text("Also remove sessions never exported to USB", style.label_size));
+                        });
+
+                        section(ui, &style, "🖼 Saved images", |ui| {
+                            let output = &mut draft.output;
+                            string_choice_row(ui, &style, "Format", &mut output.format, OUTPUT_FORMATS);
+                            match output.format.as_str() {
+                                "png" => string_choice_row(ui, &style, "PNG compression", &mut output.png_compression, PNG_COMPRESSIONS),
+                                "jpeg" | "jpg" => {
+                                    ui.add(egui::Slider::new(&mut output.jpeg_quality, 1..=100)
+                                        .text(button_text("JPEG quality", style.label_size)));
+                                }
+                                "webp" => {
+                                    ui.label(button_text("WebP images are saved lossless", style.label_size * 0.8).color(egui::Color32::GRAY));
+                                }
+                                _ => {}
+                            }
+                            ui.checkbox(&mut output.share_copy,
+                                button_text("Also save a small JPEG for sharing", style.label_size));
+                            ui.add_enabled_ui(output.share_copy, |ui| {
+                                ui.add(egui::Slider::new(&mut output.share_max_size, MIN_SHARE_SIZE..=MAX_SHARE_SIZE)
+                                    .step_by(MIN_SHARE_SIZE as f64)
+                                    .suffix(" px")
+                                    .text(button_text("Share size", style.label_size)));
+                                ui.add(egui::Slider::new(&mut output.share_quality, 1..=100)
+                                    .text(button_text("Share quality", style.label_size)));
+                            });
+                        });
+
+                        section(ui, &style, "💾 USB Export", |ui| {
+                            ui.label(button_text("Folder on the drive", style.label_size));
+                            ui.add(egui::TextEdit::singleline(&mut draft.export.folder_name)
+                                .font(egui::FontId::proportional(style.label_size))
+                                .desired_width(style.button_width - 200.0));
+                            ui.checkbox(&mut draft.export.delete_after_copy,
+                                button_text("Delete local images after copying (default)", style.label_size));
+
+                            let modes: Vec<(ExportMode, &str)> = ExportMode::ALL.iter().map(|mode| (*mode, mode.label())).collect();
+                            let mut mode = draft.export_mode();
+                            choice_row(ui, &style, "Export by default", &mut mode, &modes);
+                            if ExportMode::from_name(&draft.export.mode) != Some(mode) {
+                                draft.export.mode = mode.name().to_string();
+                            }
+                            ui.checkbox(&mut draft.export.zip_per_session,
+                                button_text("ZIP archive per session (default)", style.label_size));
+                        });
+
+                        section(ui, &style, "🔋 Battery (UPS)", |ui| {
+                            // From empty (less a margin) to full for the configured pack, 1S to 4S
+                            let (empty, full) = draft.ups_config().discharge_curve.voltage_range();
+                            let threshold_range = (empty * 0.9)..=full;
+                            let ups = &mut draft.ups;
+                            ui.checkbox(&mut ups.enabled, button_text("Monitor the UPS", style.label_size));
+                            ui.add_enabled_ui(ups.enabled, |ui| {
+                                ui.add(egui::Slider::new(&mut ups.voltage_threshold, threshold_range)
+                                    .step_by(0.05)
+                                    .suffix(" V")
+                                    .text(button_text("Shut down below", style.label_size)));
+                                ui.add(egui::Slider::new(&mut ups.warning_duration_secs, MIN_WARNING_SECS..=MAX_WARNING_SECS)
+                                    .suffix(" s")
+                                    .text(button_text("Shutdown warning", style.label_size)));
+                                ui.add(egui::Slider::new(&mut ups.check_interval_secs, 1..=60)
+                                    .suffix(" s")
+                                    .text(button_text("Check every", style.label_size)));
+                                ui.add(egui::Slider::new(&mut ups.soc_hysteresis, 0.0..=MAX_SOC_HYSTERESIS)
+                                    .step_by(0.5)
+                                    .suffix(" %")
+                                    .text(button_text("Percentage smoothing", style.label_size)));
+                            });
+                        });
+                    });
+
+                ui.add_space(style.spacing);
+
+                let validation = self.settings_draft.validate();
+                if let Err(ref problems) = validation {
+                    ui.label(egui::RichText::new(format!("⚠ {}", problems)).color(egui::Color32::from_rgb(220, 180, 50)).size(style.label_size));
+                }
+                let changed = self.settings_draft != self.config;
+
+                ui.vertical_centered(|ui| {
+                    let save_button = egui::Button::new(button_text("💾 Save", style.label_size)).fill(button_green());
+                    if ui.add_enabled_ui(changed && validation.is_ok(), |ui| {
+                        ui.add_sized([style.button_width, style.button_height], save_button)
+                    }).inner.clicked() {
+                        save = true;
+                    }
+
+                    ui.add_space(style.spacing * 0.5);
+
+                    if ui.add_sized(
+                        [style.button_width, style.button_height],
+                        egui::Button::new(button_text("↺ Reset to Defaults", style.label_size))
+                    ).clicked() {
+                        self.settings_draft = AppConfig {
+                            update: self.config.update.clone(),
+                            ..AppConfig::default()
+                        };
+                    }
+
+                    ui.add_space(style.spacing * 0.5);
+
+                    if ui.add_sized(
+                        [style.button_width, style.cancel_button_height],
+                        egui::Button::new(button_text("Cancel", style.label_size))
+                    ).clicked() {
+                        close = true;
+                    }
+                });
+            });
+
+        // Force window to top layer
+        if let Some(response) = response {
+            ctx.move_to_top(response.response.layer_id);
+        }
+
+        if save {
+            self.save_settings(ctx);
+        } else if close {
+            self.show_settings = false;
+        }
+    }
+
+    fn save_settings(&mut self, ctx: &egui::Context) {
+        let config = self.settings_draft.clone();
+        match self.config.save_edits(&config) {
+            Ok(()) => {
+                self.apply_config(ctx, config);
+                self.config_error = None;
+                // Our own write shouldn't show up as an external edit
+                self.config_watcher = ConfigWatcher::new();
+                self.show_settings = false;
+                self.export_message = Some("✓ Settings saved".to_string());
+            }
+            Err(e) => {
+                log::error!("Failed to save settings: {}", e);
+                self.export_message = Some(format!("✗ Could not save settings: {}", e));
+            }
+        }
+        self.export_message_time = Some(Instant::now());
+    }
+}
+
+/// Titled group spanning the menu width
+fn section(ui: &mut egui::Ui, style: &MenuStyle, title: &str, add_contents: impl FnOnce(&mut egui::Ui)) {
+    ui.group(|ui| {
+        ui.set_min_width(style.button_width);
+        ui.label(button_text(title, style.label_size).strong());
+        ui.separator();
+        add_contents(ui);
+    });
+    ui.add_space(style.spacing * 0.5);
+}
+
+/// Row of large toggle buttons, one per option
+fn choice_row<T: PartialEq + Copy>(ui: &mut egui::Ui, style: &MenuStyle, label: &str, value: &mut T, options: &[(T, &str)]) {
+    ui.label(button_text(label, style.label_size));
+    ui.horizontal_wrapped(|ui| {
+        for &(option, text) in options {
+            let button = egui::SelectableLabel::new(*value == option, button_text(text, style.label_size));
+            if ui.add_sized([140.0, 56.0], button).clicked() {
+                *value = option;
+            }
+        }
+    });
+}
+
+/// choice_row for a string setting; an unlisted value leaves every button unselected
+fn string_choice_row(ui: &mut egui::Ui, style: &MenuStyle, label: &str, value: &mut String, options: &[(&'static str, &str)]) {
+    let mut choice = options.iter().map(|(name, _)| *name).find(|name| name == value).unwrap_or("");
+    choice_row(ui, style, label, &mut choice, options);
+    if choice != value.as_str() && !choice.is_empty() {
+        *value = choice.to_string();
+    }
+}
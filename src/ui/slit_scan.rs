@@ -0,0 +1,76 @@
+use crate::PixelSorterApp;
+use crate::processing::slit_scan::SlitScan;
+use crate::ui::indicators::StatusBanner;
+use eframe::egui;
+use std::time::Instant;
+
+impl PixelSorterApp {
+    pub fn start_slit_scan(&mut self) {
+        log::info!("Slit-scan started ({:?})", self.slit_orientation);
+        self.slit_scan = Some(SlitScan::new(self.slit_orientation));
+    }
+
+    /// Feed every new stream frame into the slit-scan and show the image as it builds
+    /// (replaces the normal preview update while scanning)
+    pub fn update_slit_scan(&mut self, ctx: &egui::Context) {
+        let Some(camera) = self.camera_controller.clone() else {
+            self.slit_scan = None;
+            return;
+        };
+
+        let frames = match camera.try_write() {
+            Ok(mut camera_lock) => camera_lock.take_stream_frames(),
+            Err(_) => return,
+        };
+
+        let Some(scan) = self.slit_scan.as_mut() else {
+            return;
+        };
+        for frame in &frames {
+            scan.push_frame(frame);
+        }
+
+        if scan.is_complete() {
+            self.finish_slit_scan(ctx);
+        } else if !frames.is_empty() {
+            if let Some(partial) = scan.to_image() {
+                self.update_camera_texture(ctx, &partial);
+            }
+        }
+    }
+
+    /// Stop scanning and send the (possibly partial) result to the Edit phase
+    pub fn finish_slit_scan(&mut self, ctx: &egui::Context) {
+        let Some(scan) = self.slit_scan.take() else {
+            return;
+        };
+
+        // Preview texture held the partial scan; let the live feed replace it next time
+        self.camera_texture = None;
+
+        match scan.to_image() {
+            Some(image) => {
+                log::info!("Slit-scan finished with {} slices", scan.slices());
+                self.original_image = Some(image.clone());
+                self.processed_image = Some(image.clone());
+                self.create_processed_texture(ctx, image);
+                self.preview_mode = false;
+                self.current_phase = crate::ui::Phase::Edit;
+            }
+            None => {
+                self.export_message = Some("✗ Slit-scan captured no frames".to_string());
+                self.export_message_time = Some(Instant::now());
+            }
+        }
+    }
+
+    /// Status line while scanning
+    pub fn slit_scan_banner(&self) -> Option<StatusBanner> {
+        let scan = self.slit_scan.as_ref()?;
+        Some(StatusBanner::info(if scan.target_slices() > 0 {
+            format!("▤ Slit-scan: {}/{} · tap shutter to finish", scan.slices(), scan.target_slices())
+        } else {
+            "▤ Slit-scan: waiting for camera…".to_string()
+        }))
+    }
+}
@@ -19,3 +19,163 @@ pub enum HandlePosition {
     BottomLeft,
     BottomRight,
 }
+
+/// What happens when the shutter button is pressed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ShootingMode {
+    /// One photo, straight to Edit
+    Single,
+    /// Several photos at an interval, then pick one for Edit
+    Burst,
+    /// Frames at a fixed interval over a set duration, assembled into an animation
+    Timelapse,
+    /// One pixel column/row from each successive stream frame
+    SlitScan,
+}
+
+impl ShootingMode {
+    pub fn all() -> &'static [ShootingMode] {
+        &[ShootingMode::Single, ShootingMode::Burst, ShootingMode::Timelapse, ShootingMode::SlitScan]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ShootingMode::Single => "Single",
+            ShootingMode::Burst => "Burst",
+            ShootingMode::Timelapse => "Lapse",
+            ShootingMode::SlitScan => "Slit",
+        }
+    }
+
+    pub fn next(&self) -> ShootingMode {
+        let all = Self::all();
+        let idx = all.iter().position(|x| x == self).unwrap();
+        all[(idx + 1) % all.len()]
+    }
+}
+
+/// Self-timer delay before the shutter fires
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SelfTimer {
+    Off,
+    Three,
+    Five,
+    Ten,
+}
+
+impl SelfTimer {
+    pub fn all() -> &'static [SelfTimer] {
+        &[SelfTimer::Off, SelfTimer::Three, SelfTimer::Five, SelfTimer::Ten]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SelfTimer::Off => "Timer",
+            SelfTimer::Three => "3s",
+            SelfTimer::Five => "5s",
+            SelfTimer::Ten => "10s",
+        }
+    }
+
+    pub fn seconds(&self) -> u64 {
+        match self {
+            SelfTimer::Off => 0,
+            SelfTimer::Three => 3,
+            SelfTimer::Five => 5,
+            SelfTimer::Ten => 10,
+        }
+    }
+
+    pub fn next(&self) -> SelfTimer {
+        let all = Self::all();
+        let idx = all.iter().position(|x| x == self).unwrap();
+        all[(idx + 1) % all.len()]
+    }
+}
+
+/// Frames per burst, cycled by the burst count button
+pub const BURST_COUNTS: &[u32] = &[3, 5, 10];
+/// Time between burst frames in milliseconds, cycled by the burst interval button
+pub const BURST_INTERVALS_MS: &[u64] = &[250, 500, 1000, 2000];
+
+/// Time between time-lapse frames, cycled by the time-lapse interval button
+pub const TIMELAPSE_INTERVALS: &[std::time::Duration] = &[
+    std::time::Duration::from_secs(2),
+    std::time::Duration::from_secs(5),
+    std::time::Duration::from_secs(10),
+    std::time::Duration::from_secs(30),
+    std::time::Duration::from_secs(60),
+];
+/// Length of a time-lapse, cycled by the time-lapse duration button
+pub const TIMELAPSE_DURATIONS: &[std::time::Duration] = &[
+    std::time::Duration::from_secs(5 * 60),
+    std::time::Duration::from_secs(15 * 60),
+    std::time::Duration::from_secs(30 * 60),
+    std::time::Duration::from_secs(60 * 60),
+    std::time::Duration::from_secs(120 * 60),
+];
+
+/// Burst capture in progress
+pub struct BurstState {
+    pub frames: Vec<image::RgbImage>,
+    pub next_shot: std::time::Instant,
+    /// Number of this burst in the session (burst_NN_...), picked when the first frame is saved
+    pub sequence: Option<u32>,
+}
+
+/// One time-lapse frame for the worker to optionally sort, and save
+pub struct TimelapseShot {
+    pub frame: image::RgbImage,
+    pub path: std::path::PathBuf,
+    pub sort: Option<(crate::processing::SortingAlgorithm, crate::processing::SortingParameters)>,
+}
+
+/// Time-lapse recording in progress
+pub struct TimelapseState {
+    pub session_dir: std::path::PathBuf,
+    /// Number of this run in the session (timelapse_NN_...)
+    pub sequence: u32,
+    /// Frames saved so far
+    pub frames: Vec<std::path::PathBuf>,
+    /// Frames requested so far, saved or not
+    pub shots: usize,
+    pub started: std::time::Instant,
+    pub next_shot: std::time::Instant,
+    /// Sorts and saves one frame at a time off the UI thread
+    pub worker: std::thread::JoinHandle<()>,
+    pub shot_sender: std::sync::mpsc::Sender<TimelapseShot>,
+    pub shot_results: std::sync::mpsc::Receiver<Result<std::path::PathBuf, String>>,
+    /// Frames sent to the worker that haven't reported back
+    pub in_flight: usize,
+}
+
+/// Where the image browser is looking
+#[derive(Debug, Clone, PartialEq)]
+pub enum BrowserSource {
+    /// Every image on a removable drive, copied into a new session when chosen
+    Drive { mount_point: std::path::PathBuf, label: String },
+    /// The output folder and configured image folders, browsed folder by folder
+    Local,
+}
+
+/// Touch image browser for opening or importing images
+pub struct BrowserState {
+    pub source: BrowserSource,
+    /// Top-level folders (local browsing only)
+    pub roots: Vec<std::path::PathBuf>,
+    /// Folder shown; None lists the roots (or everything found on a drive)
+    pub current_dir: Option<std::path::PathBuf>,
+    pub folders: Vec<std::path::PathBuf>,
+    pub images: Vec<crate::session::import::ImageFile>,
+    pub sort: crate::session::import::SortOrder,
+    /// In the order they were tapped; the first one opens in Edit
+    pub selected: Vec<std::path::PathBuf>,
+    /// None for files that couldn't be decoded, so they aren't waited for
+    pub thumbnails: std::collections::HashMap<std::path::PathBuf, Option<eframe::egui::TextureHandle>>,
+    /// Drive search running in the background (drive browsing only)
+    pub search_result: Option<std::sync::mpsc::Receiver<Vec<crate::session::import::ImageFile>>>,
+    /// Thumbnails decoded in the background for the current folder (None if decoding failed)
+    pub thumbnail_receiver: std::sync::mpsc::Receiver<(std::path::PathBuf, Option<eframe::egui::ColorImage>)>,
+    /// Copy into the new session, while it runs
+    pub copy_result: Option<std::sync::mpsc::Receiver<Result<Vec<std::path::PathBuf>, String>>>,
+}
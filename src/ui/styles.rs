@@ -215,6 +215,32 @@ impl MenuStyle {
         }
     }
 
+    /// Settings screen style (developer menu width, room for rows of choices)
+    pub fn settings() -> Self {
+        Self {
+            width: 933.0,
+            button_width: 800.0,
+            button_height: 70.0,
+            cancel_button_height: 80.0,
+            spacing: 20.0,
+            heading_size: 32.0,
+            label_size: 22.0,
+        }
+    }
+
+    /// Image browser style (full width for rows of thumbnails)
+    pub fn image_browser() -> Self {
+        Self {
+            width: 933.0,
+            button_width: 800.0,
+            button_height: 70.0,
+            cancel_button_height: 60.0,
+            spacing: 15.0,
+            heading_size: 28.0,
+            label_size: 22.0,
+        }
+    }
+
     /// USB export dialog style (smaller, focused)
     pub fn usb_export() -> Self {
         Self {
@@ -0,0 +1,210 @@
+use crate::PixelSorterApp;
+use crate::processing::PixelSorter;
+use crate::session::writer::write_in_place;
+use crate::ui::indicators::StatusBanner;
+use crate::ui::state::{TimelapseShot, TimelapseState};
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+
+const TIMELAPSE_GIF_FRAME_MS: u32 = 100;   // Playback speed of the assembled animation (10 FPS)
+const TIMELAPSE_GIF_MAX_WIDTH: u32 = 640;  // Width of the assembled animation
+
+impl PixelSorterApp {
+    pub fn start_timelapse(&mut self) {
+        // Each run in a session gets its own number so it doesn't overwrite the previous one
+        let started = self.ensure_session_dir().and_then(|dir| Ok((dir, self.next_sequence("timelapse_")?)));
+        let (session_dir, sequence) = match started {
+            Ok(started) => started,
+            Err(e) => {
+                log::error!("Cannot start time-lapse: {}", e);
+                self.export_message = Some(format!("✗ Time-lapse failed: {}", e));
+                self.export_message_time = Some(Instant::now());
+                return;
+            }
+        };
+
+        log::info!(
+            "Time-lapse {} started: every {}s for {}s into {}",
+            sequence,
+            self.timelapse_interval.as_secs(),
+            self.timelapse_duration.as_secs(),
+            session_dir.display()
+        );
+
+        let (shot_sender, shots) = mpsc::channel();
+        let (result_sender, shot_results) = mpsc::channel();
+        let pixel_sorter = Arc::clone(&self.pixel_sorter);
+        let worker = std::thread::spawn(move || run_timelapse_worker(pixel_sorter, shots, result_sender));
+
+        let now = Instant::now();
+        self.timelapse = Some(TimelapseState {
+            session_dir,
+            sequence,
+            frames: Vec::new(),
+            shots: 0,
+            started: now,
+            next_shot: now,
+            worker,
+            shot_sender,
+            shot_results,
+            in_flight: 0,
+        });
+    }
+
+    /// Collect saved frames, request due ones and finish when the duration is reached
+    /// (called every frame in Input phase)
+    pub fn update_timelapse(&mut self) {
+        let Some(state) = self.timelapse.as_mut() else {
+            return;
+        };
+
+        while let Ok(result) = state.shot_results.try_recv() {
+            state.in_flight = state.in_flight.saturating_sub(1);
+            match result {
+                Ok(path) => state.frames.push(path),
+                Err(e) => log::warn!("Time-lapse frame skipped: {}", e),
+            }
+        }
+
+        if state.started.elapsed() >= self.timelapse_duration {
+            self.stop_timelapse();
+        } else if Instant::now() >= state.next_shot {
+            self.take_timelapse_frame();
+        }
+    }
+
+    fn take_timelapse_frame(&mut self) {
+        let Some(camera) = self.camera_controller.clone() else {
+            self.stop_timelapse();
+            return;
+        };
+
+        // Frames come from the live stream: an HQ still every interval would freeze the UI
+        let frame = match camera.try_write() {
+            Ok(mut camera_lock) => camera_lock.capture_stream_frame(),
+            Err(_) => return, // Camera busy, retry next frame
+        };
+
+        let sort = self.timelapse_sort_frames.then(|| (self.current_algorithm, self.sorting_params.clone()));
+        let interval = self.timelapse_interval;
+
+        let Some(state) = self.timelapse.as_mut() else {
+            return;
+        };
+        // Schedule from the previous slot so the interval doesn't drift with capture time,
+        // but never try to catch up on missed slots
+        state.next_shot += interval;
+        if state.next_shot < Instant::now() {
+            state.next_shot = Instant::now() + interval;
+        }
+
+        let frame = match frame {
+            Ok(frame) => frame,
+            Err(e) => {
+                log::warn!("Time-lapse frame skipped: {}", e);
+                return;
+            }
+        };
+        // Sorting slower than the interval skips this slot instead of queueing frames up behind it
+        if state.in_flight > 0 {
+            log::warn!("Time-lapse frame skipped: previous frame still being saved");
+            return;
+        }
+
+        state.shots += 1;
+        let path = state.session_dir.join(format!("timelapse_{:02}_{:04}.png", state.sequence, state.shots));
+        if state.shot_sender.send(TimelapseShot { frame, path, sort }).is_ok() {
+            state.in_flight += 1;
+        }
+    }
+
+    /// End the recording and assemble the animation in the background
+    pub fn stop_timelapse(&mut self) {
+        let Some(state) = self.timelapse.take() else {
+            return;
+        };
+
+        log::info!("Time-lapse finished with {} frames, assembling animation", state.frames.len() + state.in_flight);
+
+        let (sender, receiver) = mpsc::channel();
+        self.timelapse_result = Some(receiver);
+
+        std::thread::spawn(move || {
+            // Let the worker finish the frames it was given, then pick those up too
+            let TimelapseState { session_dir, sequence, mut frames, worker, shot_sender, shot_results, .. } = state;
+            drop(shot_sender);
+            let _ = worker.join();
+            frames.extend(shot_results.try_iter().filter_map(Result::ok));
+
+            let output = session_dir.join(format!("timelapse_{:02}.gif", sequence));
+            let result = write_in_place(&output, |temp| {
+                crate::processing::animation::encode_gif(&frames, temp, TIMELAPSE_GIF_FRAME_MS, TIMELAPSE_GIF_MAX_WIDTH)
+            })
+            .map(|()| output)
+            .map_err(|e| e.to_string());
+            let _ = sender.send(result);
+        });
+    }
+
+    /// Report when the background animation assembly finishes
+    pub fn poll_timelapse_result(&mut self) {
+        let Some(receiver) = &self.timelapse_result else {
+            return;
+        };
+
+        let result: Result<PathBuf, String> = match receiver.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err("Assembly thread stopped".to_string()),
+        };
+        self.timelapse_result = None;
+
+        match result {
+            Ok(path) => {
+                log::info!("Time-lapse animation saved to {}", path.display());
+                self.export_message = Some("✓ Time-lapse saved!".to_string());
+            }
+            Err(e) => {
+                log::error!("Time-lapse assembly failed: {}", e);
+                self.export_message = Some(format!("✗ Time-lapse failed: {}", e));
+            }
+        }
+        self.export_message_time = Some(Instant::now());
+    }
+
+    /// Status line while recording
+    pub fn timelapse_banner(&self) -> Option<StatusBanner> {
+        let state = self.timelapse.as_ref()?;
+
+        let elapsed = state.started.elapsed();
+        let remaining = self.timelapse_duration.saturating_sub(elapsed).as_secs();
+        let next_in = state.next_shot.saturating_duration_since(Instant::now()).as_secs();
+        Some(StatusBanner::info(format!(
+            "⏱ Time-lapse: {} frames · {}:{:02} left · next in {}s",
+            state.frames.len(),
+            remaining / 60,
+            remaining % 60,
+            next_in
+        )))
+    }
+}
+
+/// Sort each frame if asked, save it and report back, one at a time
+fn run_timelapse_worker(
+    pixel_sorter: Arc<PixelSorter>,
+    shots: mpsc::Receiver<TimelapseShot>,
+    results: mpsc::Sender<Result<PathBuf, String>>,
+) {
+    for shot in shots {
+        let output = match &shot.sort {
+            Some((algorithm, params)) => pixel_sorter.sort_pixels(&shot.frame, *algorithm, params).unwrap_or(shot.frame),
+            None => shot.frame,
+        };
+        // Written under a temp name so an export never copies a half-written frame
+        let result = write_in_place(&shot.path, |temp| Ok(output.save_with_format(temp, image::ImageFormat::Png)?))
+            .map(|()| shot.path.clone())
+            .map_err(|e| format!("could not save {}: {}", shot.path.display(), e));
+        let _ = results.send(result);
+    }
+}